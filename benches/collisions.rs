@@ -0,0 +1,80 @@
+//! Compares collision checks against big collections with and without a spatial hash.
+//!
+//! Run with `cargo bench --bench collisions`.
+
+use cat_box::{
+    check_for_collision_between_collections, check_for_collision_with_collection,
+    objects::sprite::{Sprite, SpriteCollection},
+    sdl2::rect::Rect,
+};
+use std::time::{Duration, Instant};
+
+const WORLD_SIZE: i32 = 4000;
+const SPRITE_SIZE: u32 = 32;
+
+/// A cheap, repeatable stand-in for random positions.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> i32 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 33) % WORLD_SIZE as u64) as i32
+    }
+}
+
+fn sprites(count: usize, rng: &mut Lcg) -> SpriteCollection {
+    let bytes = include_bytes!("../duck.png");
+    let mut collection = SpriteCollection::with_capacity(count);
+    for _ in 0..count {
+        let mut sprite = Sprite::from_bytes(bytes, 0, 0).unwrap();
+        sprite.rect = Rect::new(rng.next(), rng.next(), SPRITE_SIZE, SPRITE_SIZE);
+        collection.push(sprite);
+    }
+    collection
+}
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let hits = f();
+    (start.elapsed(), hits)
+}
+
+fn main() {
+    let mut rng = Lcg(0x0123_4567);
+
+    for &count in &[1_000, 5_000, 10_000] {
+        let mut walls = sprites(count, &mut rng);
+        let probes = sprites(1_000, &mut rng);
+
+        let query = |walls: &SpriteCollection| {
+            probes
+                .inner()
+                .iter()
+                .map(|probe| check_for_collision_with_collection(probe, walls).len())
+                .sum()
+        };
+        let (brute, brute_hits) = time(|| query(&walls));
+        walls.set_spatial_hash(Some(SPRITE_SIZE * 2));
+        let (hashed, hashed_hits) = time(|| query(&walls));
+        assert_eq!(brute_hits, hashed_hits);
+        println!(
+            "1000 sprites vs {count:>5}: {brute:>10.2?} without a hash, {hashed:>10.2?} with one ({:.1}x)",
+            brute.as_secs_f64() / hashed.as_secs_f64()
+        );
+
+        walls.set_spatial_hash(None);
+        let (brute, brute_hits) =
+            time(|| check_for_collision_between_collections(&probes, &walls).len());
+        walls.set_spatial_hash(Some(SPRITE_SIZE * 2));
+        let (hashed, hashed_hits) =
+            time(|| check_for_collision_between_collections(&probes, &walls).len());
+        assert_eq!(brute_hits, hashed_hits);
+        println!(
+            "collections 1000 x {count:>5}: {brute:>10.2?} without a hash, {hashed:>10.2?} with one ({:.1}x)",
+            brute.as_secs_f64() / hashed.as_secs_f64()
+        );
+    }
+}
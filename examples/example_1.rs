@@ -31,10 +31,19 @@ fn main() {
     }
     let mut m = MouseRepr::empty();
     #[cfg(feature = "audio")]
-    cat_box::play("output.mp3", 120);
+    let song =
+        cat_box::audio::music::Music::from_bytes(include_bytes!("../output.mp3")).looping(true);
     game.run(|ctx| {
         let (_, _, _) = ctx.inner();
 
+        #[cfg(feature = "audio")]
+        if !ctx.audio().music().is_playing() {
+            ctx.audio()
+                .music()
+                .play(&song, std::time::Duration::from_secs(1))
+                .unwrap();
+        }
+
         //let win = b.window_mut();
         /* let instance_schtuff = win.vulkan_instance_extensions().unwrap(); */
 
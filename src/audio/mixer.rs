@@ -0,0 +1,365 @@
+//! Mixer buses, for setting the volume of each kind of sound separately.
+//!
+//! Every sound plays through one [`Bus`]: music through [`Bus::Music`], and sound effects
+//! through [`Bus::Sfx`] unless they're played with [`Audio::play_on()`](super::Audio::play_on()).
+//! Everything also goes through [`Bus::Master`].
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, audio::{mixer::{Bus, MixerSettings}, sound::SoundEffect}};
+//! # use std::time::Duration;
+//! # let game = Game::new("mixer demo", 1000, 800);
+//! let line = SoundEffect::load("hello.ogg").unwrap();
+//! let settings = MixerSettings::load("mixer.cfg").unwrap_or_default();
+//! let mut first_frame = true;
+//!
+//! game.run(|ctx| {
+//!     let mixer = ctx.audio().mixer();
+//!     if first_frame {
+//!         mixer.set_settings(settings);
+//!         // Turn the music down to a fifth while anyone is talking
+//!         mixer.duck(Bus::Music, Bus::Voice, 0.2, Duration::from_millis(300));
+//!         first_frame = false;
+//!     }
+//!
+//!     if ctx.input().just_pressed(Scancode::M) {
+//!         let muted = ctx.audio().mixer().is_muted(Bus::Music);
+//!         ctx.audio().mixer().set_muted(Bus::Music, !muted);
+//!     }
+//!     if ctx.input().just_pressed(Scancode::T) {
+//!         ctx.audio().play_on(&line, Bus::Voice);
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! [`MixerSettings`] save to a config file with one bus per line, and `muted` after the volume
+//! if the bus is muted:
+//!
+//! ```text
+//! master = 0.8
+//! music = 0.5, muted
+//! sfx = 1
+//! voice = 1
+//! ```
+
+use std::{fmt, path::Path, str::FromStr, time::Duration};
+
+use crate::{CatboxError, Result};
+
+/// A mixer bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bus {
+    /// Every other bus goes through this one.
+    Master,
+    Music,
+    Sfx,
+    Voice,
+}
+
+impl Bus {
+    /// Every bus, in the order they're saved in.
+    pub const ALL: [Bus; 4] = [Bus::Master, Bus::Music, Bus::Sfx, Bus::Voice];
+
+    /// The bus's name in config files.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Master => "master",
+            Self::Music => "music",
+            Self::Sfx => "sfx",
+            Self::Voice => "voice",
+        }
+    }
+
+    pub(crate) fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Bus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Bus {
+    type Err = CatboxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|bus| bus.name() == s)
+            .ok_or_else(|| CatboxError::Parse(format!("unknown bus `{s}`")))
+    }
+}
+
+/// The volume of every bus and whether it's muted, which players would expect to be saved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MixerSettings {
+    volumes: [f32; 4],
+    muted: [bool; 4],
+}
+
+impl MixerSettings {
+    /// Every bus at full volume, and none muted.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            volumes: [1.0; 4],
+            muted: [false; 4],
+        }
+    }
+
+    /// Load settings from a config file in the format described in the [module docs](self).
+    ///
+    /// Buses missing from the file are left at full volume.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| CatboxError::asset(path, e))?
+            .parse()
+            .map_err(|e| CatboxError::asset(path, e))
+    }
+
+    /// Save these settings to a config file, so they can be loaded with [`Self::load()`].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()).map_err(|e| CatboxError::asset(path, e))
+    }
+
+    /// The volume of `bus`, from `0.0` to `1.0`.
+    #[must_use]
+    pub fn volume(&self, bus: Bus) -> f32 {
+        self.volumes[bus.index()]
+    }
+
+    /// Set the volume of `bus`, clamped to between `0.0` and `1.0`.
+    pub fn set_volume(&mut self, bus: Bus, volume: f32) {
+        self.volumes[bus.index()] = volume.clamp(0.0, 1.0);
+    }
+
+    /// Whether `bus` is muted.
+    #[must_use]
+    pub fn is_muted(&self, bus: Bus) -> bool {
+        self.muted[bus.index()]
+    }
+
+    /// Mute or unmute `bus`, keeping its volume for when it's unmuted.
+    pub fn set_muted(&mut self, bus: Bus, muted: bool) {
+        self.muted[bus.index()] = muted;
+    }
+
+    /// The volume of `bus` on its own, or `0.0` if it's muted.
+    fn gain(&self, bus: Bus) -> f32 {
+        if self.is_muted(bus) {
+            0.0
+        } else {
+            self.volume(bus)
+        }
+    }
+}
+
+impl Default for MixerSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for MixerSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bus in Bus::ALL {
+            write!(f, "{bus} = {}", self.volume(bus))?;
+            if self.is_muted(bus) {
+                f.write_str(", muted")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for MixerSettings {
+    type Err = CatboxError;
+
+    /// Parse settings from the config file format described in the [module docs](self).
+    /// ```
+    /// # use cat_box::audio::mixer::{Bus, MixerSettings};
+    /// let settings: MixerSettings = "master = 0.8\nmusic = 0.5, muted".parse().unwrap();
+    /// assert_eq!(settings.volume(Bus::Music), 0.5);
+    /// assert!(settings.is_muted(Bus::Music));
+    /// assert_eq!(settings.volume(Bus::Sfx), 1.0);
+    /// assert_eq!(settings.to_string().parse::<MixerSettings>().unwrap(), settings);
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let mut settings = Self::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (bus, value) = line.split_once('=').ok_or_else(|| {
+                CatboxError::Parse(format!("line {}: expected `bus = volume`", i + 1))
+            })?;
+            let bus: Bus = bus
+                .parse()
+                .map_err(|e| CatboxError::Parse(format!("line {}: {}", i + 1, e)))?;
+
+            let (volume, muted) = match value.split_once(',') {
+                Some((volume, flag)) if flag.trim() == "muted" => (volume, true),
+                Some((_, flag)) => {
+                    return Err(CatboxError::Parse(format!(
+                        "line {}: unknown flag `{}`",
+                        i + 1,
+                        flag.trim()
+                    )))
+                }
+                None => (value, false),
+            };
+            let volume: f32 = volume.trim().parse().map_err(|_| {
+                CatboxError::Parse(format!(
+                    "line {}: invalid volume `{}`",
+                    i + 1,
+                    volume.trim()
+                ))
+            })?;
+
+            settings.set_volume(bus, volume);
+            settings.set_muted(bus, muted);
+        }
+
+        Ok(settings)
+    }
+}
+
+/// Lowers `target` while anything is playing on `trigger`.
+#[derive(Clone, Copy, Debug)]
+struct Ducking {
+    target: Bus,
+    trigger: Bus,
+    gain: f32,
+    fade: Duration,
+}
+
+/// The mixer, with the [`MixerSettings`] and any ducking between buses.
+///
+/// Get this with [`Audio::mixer()`](super::Audio::mixer()). Changes are heard from the next
+/// frame on.
+pub struct Mixer {
+    settings: MixerSettings,
+    ducking: Vec<Ducking>,
+    // How far each bus is ducked right now, `1.0` being not at all.
+    duck_levels: [f32; 4],
+}
+
+impl Mixer {
+    pub(crate) fn new() -> Self {
+        Self {
+            settings: MixerSettings::new(),
+            ducking: Vec::new(),
+            duck_levels: [1.0; 4],
+        }
+    }
+
+    /// The volume and mute settings of every bus.
+    #[must_use]
+    pub fn settings(&self) -> MixerSettings {
+        self.settings
+    }
+
+    /// Replace the volume and mute settings of every bus, like with ones loaded from a file.
+    pub fn set_settings(&mut self, settings: MixerSettings) {
+        self.settings = settings;
+    }
+
+    /// The volume of `bus`, from `0.0` to `1.0`.
+    #[must_use]
+    pub fn volume(&self, bus: Bus) -> f32 {
+        self.settings.volume(bus)
+    }
+
+    /// Set the volume of `bus`, clamped to between `0.0` and `1.0`.
+    pub fn set_volume(&mut self, bus: Bus, volume: f32) {
+        self.settings.set_volume(bus, volume);
+    }
+
+    /// Whether `bus` is muted.
+    #[must_use]
+    pub fn is_muted(&self, bus: Bus) -> bool {
+        self.settings.is_muted(bus)
+    }
+
+    /// Mute or unmute `bus`, keeping its volume for when it's unmuted.
+    pub fn set_muted(&mut self, bus: Bus, muted: bool) {
+        self.settings.set_muted(bus, muted);
+    }
+
+    /// Lower `target` to `gain` times its volume while anything is playing on `trigger`, fading
+    /// down and back up over `fade`.
+    ///
+    /// If `target` is ducked by several buses at once, the quietest gain wins.
+    pub fn duck(&mut self, target: Bus, trigger: Bus, gain: f32, fade: Duration) {
+        self.ducking
+            .retain(|d| d.target != target || d.trigger != trigger);
+        self.ducking.push(Ducking {
+            target,
+            trigger,
+            gain: gain.clamp(0.0, 1.0),
+            fade,
+        });
+    }
+
+    /// Stop ducking every bus.
+    pub fn clear_ducking(&mut self) {
+        self.ducking.clear();
+    }
+
+    /// The volume everything on `bus` is played at, counting the master bus, muting and ducking.
+    #[must_use]
+    pub fn gain(&self, bus: Bus) -> f32 {
+        let level = |bus: Bus| self.settings.gain(bus) * self.duck_levels[bus.index()];
+
+        match bus {
+            Bus::Master => level(Bus::Master),
+            bus => level(Bus::Master) * level(bus),
+        }
+    }
+
+    /// Fade the ducked buses by `dt` seconds, given which buses have something playing.
+    pub(crate) fn update(&mut self, dt: f64, active: [bool; 4]) {
+        for bus in Bus::ALL {
+            let target = self
+                .ducking
+                .iter()
+                .filter(|d| d.target == bus && active[d.trigger.index()])
+                .map(|d| d.gain)
+                .fold(1.0, f32::min);
+            // Fade over the longest of the rules that apply, or the ones that just stopped.
+            let fade = self
+                .ducking
+                .iter()
+                .filter(|d| d.target == bus)
+                .map(|d| d.fade)
+                .max()
+                .unwrap_or_default();
+
+            let level = &mut self.duck_levels[bus.index()];
+            if fade.is_zero() {
+                *level = target;
+                continue;
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let step = (dt / fade.as_secs_f64()) as f32;
+            *level = if *level < target {
+                (*level + step).min(target)
+            } else {
+                (*level - step).max(target)
+            };
+        }
+    }
+}
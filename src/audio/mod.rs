@@ -0,0 +1,379 @@
+//! Sound, through one shared audio output.
+//!
+//! The [`Audio`] on the [`Context`](crate::Context) opens the default output device the first
+//! time it's used. Sounds are loaded once as [`SoundEffect`](sound::SoundEffect)s, and each call to
+//! [`Audio::play()`] gives back a [`Playback`] handle for controlling that one instance.
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, audio::sound::SoundEffect};
+//! # let game = Game::new("audio demo", 1000, 800);
+//! let quack = SoundEffect::load("quack.wav").unwrap();
+//! let engine = SoundEffect::load("engine.ogg").unwrap();
+//! let mut engine_playback = None;
+//!
+//! game.run(|ctx| {
+//!     if ctx.input().just_pressed(Scancode::Space) {
+//!         ctx.audio().play(&quack);
+//!     }
+//!
+//!     if ctx.input().just_pressed(Scancode::W) {
+//!         let playback = ctx.audio().play(&engine);
+//!         playback.set_looping(true);
+//!         playback.set_volume(0.5);
+//!         engine_playback = Some(playback);
+//!     }
+//!     if ctx.input().just_released(Scancode::W) {
+//!         if let Some(playback) = engine_playback.take() {
+//!             playback.stop();
+//!         }
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! Every sound plays through a [mixer bus](mixer::Bus), so players can turn music, sound effects
+//! and voices up or down separately. See [`mixer`] for more info.
+//!
+//! Sounds can also be given a position in the world, to be panned and quietened based on where
+//! they are relative to the [`Listener`](spatial::Listener). See [`spatial`] for more info.
+//!
+//! Background music is streamed rather than loaded up front, and has its own channel at
+//! [`Audio::music()`]. See [`music`] for more info.
+//!
+//! If there is no audio device, like on a CI runner, a null output is used instead. Everything
+//! still works, but nothing is heard and sounds never finish on their own.
+
+pub mod mixer;
+pub mod music;
+pub mod sound;
+pub mod spatial;
+
+use rodio::{source::ChannelVolume, OutputStream, OutputStreamHandle, Sink, Source};
+use std::{
+    cell::Cell,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::math::vec2::Vec2;
+
+use mixer::{Bus, Mixer};
+use music::MusicPlayer;
+use sound::SoundEffect;
+use spatial::Listener;
+
+/// The audio output, and every sound playing on it.
+///
+/// Get this with [`Context::audio()`](crate::Context::audio()).
+pub struct Audio {
+    playing: Vec<Rc<Instance>>,
+    music: MusicPlayer,
+    mixer: Mixer,
+    listener: Listener,
+    last_update: Instant,
+    // The stream has to outlive every sink playing on it.
+    output: Option<(OutputStream, OutputStreamHandle)>,
+}
+
+impl Audio {
+    /// Open the default output device, falling back to a [null output](Self::null()) if there
+    /// isn't one.
+    #[must_use]
+    pub fn new() -> Self {
+        let output = OutputStream::try_default().ok();
+        Self {
+            playing: Vec::new(),
+            music: MusicPlayer::new(output.as_ref().map(|(_, handle)| handle.clone())),
+            mixer: Mixer::new(),
+            listener: Listener::new(),
+            last_update: Instant::now(),
+            output,
+        }
+    }
+
+    /// Create an audio output that doesn't play anything, for headless contexts and tests.
+    /// ```
+    /// # use cat_box::audio::Audio;
+    /// let audio = Audio::null();
+    /// assert!(audio.is_null());
+    /// ```
+    #[must_use]
+    pub fn null() -> Self {
+        Self {
+            playing: Vec::new(),
+            music: MusicPlayer::new(None),
+            mixer: Mixer::new(),
+            listener: Listener::new(),
+            last_update: Instant::now(),
+            output: None,
+        }
+    }
+
+    /// Whether this is a null output, either because there was no audio device or because it
+    /// was created with [`Self::null()`].
+    #[must_use]
+    pub fn is_null(&self) -> bool {
+        self.output.is_none()
+    }
+
+    /// Start playing a sound on the [`Bus::Sfx`] bus, returning a handle to control it.
+    ///
+    /// The sound keeps playing if the handle is dropped.
+    pub fn play(&mut self, sound: &SoundEffect) -> Playback {
+        self.play_on(sound, Bus::Sfx)
+    }
+
+    /// Start playing a sound on `bus`, returning a handle to control it.
+    pub fn play_on(&mut self, sound: &SoundEffect, bus: Bus) -> Playback {
+        let playback = self.new_playback(bus, None);
+        let sink = &playback.instance.sink;
+        sink.append(sound.source(playback.looping.clone()));
+        playback
+    }
+
+    /// Start playing a sound at `position` in the world on the [`Bus::Sfx`] bus, returning a
+    /// handle to control it.
+    ///
+    /// The sound is mixed down to mono, then panned based on where it is relative to the
+    /// [listener](Self::listener()).
+    pub fn play_at<V: Into<Vec2>>(&mut self, sound: &SoundEffect, position: V) -> Playback {
+        self.play_on_at(sound, Bus::Sfx, position)
+    }
+
+    /// Start playing a sound at `position` in the world on `bus`, returning a handle to control
+    /// it.
+    pub fn play_on_at<V: Into<Vec2>>(
+        &mut self,
+        sound: &SoundEffect,
+        bus: Bus,
+        position: V,
+    ) -> Playback {
+        let position = position.into();
+        let gains = self.listener.ear_gains(position);
+        let ears = Arc::new(Mutex::new(gains));
+        let playback = self.new_playback(
+            bus,
+            Some(Spatial {
+                position: Cell::new(position),
+                ears: ears.clone(),
+            }),
+        );
+
+        let source = ChannelVolume::new(sound.source(playback.looping.clone()), gains.to_vec())
+            // Pick up the ear gains from the game loop, like rodio's `SpatialSink` does
+            .periodic_access(Duration::from_millis(5), move |source| {
+                if let Ok(ears) = ears.lock() {
+                    source.set_volume(0, ears[0]);
+                    source.set_volume(1, ears[1]);
+                }
+            });
+        playback.instance.sink.append(source);
+        playback
+    }
+
+    /// Get the music channel.
+    pub fn music(&mut self) -> &mut MusicPlayer {
+        &mut self.music
+    }
+
+    /// Get the mixer, to change the volume of each bus.
+    pub fn mixer(&mut self) -> &mut Mixer {
+        &mut self.mixer
+    }
+
+    /// Get the listener that positional sounds are heard from.
+    pub fn listener(&mut self) -> &mut Listener {
+        &mut self.listener
+    }
+
+    /// Stop every sound. The music keeps playing.
+    pub fn stop_all(&mut self) {
+        for instance in self.playing.drain(..) {
+            instance.sink.stop();
+        }
+    }
+
+    /// The number of sounds that are playing or paused.
+    #[must_use]
+    pub fn playing_count(&self) -> usize {
+        self.playing.iter().filter(|i| !i.sink.empty()).count()
+    }
+
+    fn new_playback(&mut self, bus: Bus, spatial: Option<Spatial>) -> Playback {
+        let null = self.is_null();
+        // Sinks stop when dropped, so they're kept here until they run out. Null sinks never run
+        // out, so they're let go once nothing else can control them.
+        self.playing
+            .retain(|i| !i.sink.empty() && (!null || Rc::strong_count(i) > 1));
+
+        let instance = Rc::new(Instance {
+            sink: new_sink(self.output.as_ref().map(|(_, handle)| handle)),
+            bus,
+            volume: Cell::new(1.0),
+            bus_gain: Cell::new(self.mixer.gain(bus)),
+            spatial,
+        });
+        instance.apply_volume();
+        self.playing.push(instance.clone());
+
+        Playback {
+            instance,
+            looping: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Advance the mixer and the music's fades and queue. Called once per frame by the game
+    /// loop.
+    pub(crate) fn update(&mut self) {
+        let now = Instant::now();
+        let dt = (now - self.last_update).as_secs_f64();
+        self.last_update = now;
+
+        let mut active = [false; 4];
+        for instance in &self.playing {
+            if !instance.sink.empty() && !instance.sink.is_paused() {
+                active[instance.bus.index()] = true;
+            }
+        }
+        active[Bus::Music.index()] |= self.music.is_playing() && !self.music.is_paused();
+        self.mixer.update(dt, active);
+
+        for instance in &self.playing {
+            instance.bus_gain.set(self.mixer.gain(instance.bus));
+            instance.apply_volume();
+
+            if let Some(spatial) = &instance.spatial {
+                if let Ok(mut ears) = spatial.ears.lock() {
+                    *ears = self.listener.ear_gains(spatial.position.get());
+                }
+            }
+        }
+        self.music.set_bus_gain(self.mixer.gain(Bus::Music));
+        self.music.update();
+    }
+}
+
+/// Create a sink on `handle`, or an idle one that never plays if there's no output.
+fn new_sink(handle: Option<&OutputStreamHandle>) -> Sink {
+    handle
+        .and_then(|handle| Sink::try_new(handle).ok())
+        .unwrap_or_else(|| Sink::new_idle().0)
+}
+
+impl Default for Audio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// One sound playing on a bus.
+struct Instance {
+    sink: Sink,
+    bus: Bus,
+    volume: Cell<f32>,
+    bus_gain: Cell<f32>,
+    spatial: Option<Spatial>,
+}
+
+/// Where a positional sound is, and its volume in each ear, shared with the audio thread.
+struct Spatial {
+    position: Cell<Vec2>,
+    ears: Arc<Mutex<[f32; 2]>>,
+}
+
+impl Instance {
+    fn apply_volume(&self) {
+        self.sink
+            .set_volume(self.volume.get() * self.bus_gain.get());
+    }
+}
+
+/// A handle to one playing instance of a sound.
+pub struct Playback {
+    instance: Rc<Instance>,
+    looping: Arc<AtomicBool>,
+}
+
+impl Playback {
+    /// Resume the sound if it was paused.
+    pub fn play(&self) {
+        self.instance.sink.play();
+    }
+
+    /// Pause the sound, so that [`Self::play()`] resumes it from the same spot.
+    pub fn pause(&self) {
+        self.instance.sink.pause();
+    }
+
+    /// Stop the sound for good.
+    pub fn stop(&self) {
+        self.instance.sink.stop();
+    }
+
+    /// Set the volume, `1.0` being the sound's original volume.
+    ///
+    /// This is on top of the volume of the sound's bus.
+    pub fn set_volume(&self, volume: f32) {
+        self.instance.volume.set(volume);
+        self.instance.apply_volume();
+    }
+
+    /// The volume, `1.0` being the sound's original volume.
+    #[must_use]
+    pub fn volume(&self) -> f32 {
+        self.instance.volume.get()
+    }
+
+    /// Move a sound started with [`Audio::play_at()`], in world coordinates. This is heard from
+    /// the next frame on.
+    ///
+    /// Does nothing for sounds that weren't started with a position.
+    pub fn set_position<V: Into<Vec2>>(&self, position: V) {
+        if let Some(spatial) = &self.instance.spatial {
+            spatial.position.set(position.into());
+        }
+    }
+
+    /// Where the sound is in the world, or `None` if it wasn't started with a position.
+    #[must_use]
+    pub fn position(&self) -> Option<Vec2> {
+        self.instance.spatial.as_ref().map(|s| s.position.get())
+    }
+
+    /// The bus the sound plays through.
+    #[must_use]
+    pub fn bus(&self) -> Bus {
+        self.instance.bus
+    }
+
+    /// Set whether the sound starts over when it reaches the end.
+    ///
+    /// Turning this off while the sound is playing lets it finish the current loop.
+    pub fn set_looping(&self, looping: bool) {
+        self.looping.store(looping, Ordering::Relaxed);
+    }
+
+    /// Whether the sound starts over when it reaches the end.
+    #[must_use]
+    pub fn is_looping(&self) -> bool {
+        self.looping.load(Ordering::Relaxed)
+    }
+
+    /// Whether the sound is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.instance.sink.is_paused()
+    }
+
+    /// Whether the sound has finished playing or was stopped.
+    ///
+    /// With a null output, this is never true.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.instance.sink.empty()
+    }
+}
@@ -0,0 +1,566 @@
+//! Background music, streamed from disk while it plays.
+//!
+//! Unlike [`SoundEffect`](super::sound::SoundEffect)s, music is decoded a little at a time on
+//! the audio thread, so even long tracks take almost no memory. Only one track plays at once,
+//! apart from while crossfading into the next one.
+//!
+//! The exception is a track with [loop points](Music::with_loop_points()) after its start, which
+//! keeps the looping part in memory once it has played through it. That way each loop starts
+//! straight away instead of decoding the intro again.
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, audio::music::Music};
+//! # use std::time::Duration;
+//! # let game = Game::new("music demo", 1000, 800);
+//! // Play the intro once, then loop from 12.5 seconds in to the end
+//! let level = Music::new("level.ogg").with_loop_points(Duration::from_secs_f64(12.5), None);
+//! let boss = Music::new("boss.ogg").looping(true);
+//!
+//! game.run(|ctx| {
+//!     let music = ctx.audio().music();
+//!
+//!     if !music.is_playing() {
+//!         music.play(&level, Duration::ZERO).unwrap();
+//!     }
+//!     if ctx.input().just_pressed(Scancode::B) {
+//!         ctx.audio().music().play(&boss, Duration::from_secs(2)).unwrap();
+//!     }
+//! })
+//! .unwrap();
+//! ```
+
+use rodio::{Decoder, OutputStreamHandle, Sink, Source};
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::File,
+    io::{self, BufReader, Cursor, Read, Seek, SeekFrom},
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{CatboxError, Result};
+
+/// A music track, and how it should loop.
+///
+/// This only describes the track; the file is opened when it starts playing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Music {
+    data: MusicData,
+    looping: bool,
+    loop_start: Duration,
+    loop_end: Option<Duration>,
+}
+
+impl Music {
+    /// Creates a new track from a WAV, OGG, MP3 or FLAC file, which plays once.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self::with_data(MusicData::File(path.into()))
+    }
+
+    /// Creates a new track from the bytes of a WAV, OGG, MP3 or FLAC file, like what is returned
+    /// from `include_bytes!`.
+    ///
+    /// The bytes are kept in memory, but still only decoded a little at a time while playing.
+    /// ```no_run
+    /// # use cat_box::audio::music::Music;
+    /// let theme = Music::from_bytes(include_bytes!("../../output.mp3")).looping(true);
+    /// ```
+    pub fn from_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: B) -> Self {
+        Self::with_data(MusicData::Memory(SharedBytes(Arc::new(bytes))))
+    }
+
+    fn with_data(data: MusicData) -> Self {
+        Self {
+            data,
+            looping: false,
+            loop_start: Duration::ZERO,
+            loop_end: None,
+        }
+    }
+
+    /// Set whether the whole track starts over when it reaches the end.
+    #[must_use]
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    /// Loop the part of the track between `start` and `end`, or the end of the track if `end` is
+    /// `None`.
+    ///
+    /// Anything before `start` plays once as an intro. An `end` that isn't after `start` is
+    /// ignored, so the loop goes to the end of the track.
+    #[must_use]
+    pub fn with_loop_points(mut self, start: Duration, end: Option<Duration>) -> Self {
+        self.looping = true;
+        self.loop_start = start;
+        self.loop_end = end.filter(|&end| end > start);
+        self
+    }
+
+    /// The file the track is streamed from, or `None` if it was created with
+    /// [`Self::from_bytes()`].
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        match &self.data {
+            MusicData::File(path) => Some(path),
+            MusicData::Memory(_) => None,
+        }
+    }
+
+    /// Whether the track loops.
+    #[must_use]
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Where the loop starts and ends, if the track loops.
+    #[must_use]
+    pub fn loop_points(&self) -> Option<(Duration, Option<Duration>)> {
+        self.looping.then_some((self.loop_start, self.loop_end))
+    }
+
+    fn open(&self) -> Result<Decoder<MusicReader>> {
+        let reader = match &self.data {
+            MusicData::File(path) => {
+                let file = File::open(path).map_err(|e| CatboxError::asset(path, e))?;
+                MusicReader::File(BufReader::new(file))
+            }
+            MusicData::Memory(bytes) => MusicReader::Memory(Cursor::new(bytes.clone())),
+        };
+
+        Decoder::new(reader).map_err(|e| match &self.data {
+            MusicData::File(path) => CatboxError::asset(path, e),
+            MusicData::Memory(_) => e.into(),
+        })
+    }
+
+    fn source(&self) -> Result<MusicSource> {
+        let decoder = self.open()?;
+        let channels = decoder.channels().max(1);
+        let samples_per_second = f64::from(decoder.sample_rate()) * f64::from(channels);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let to_samples = |time: Duration| {
+            // Round down to a whole frame, so the channels don't get swapped when looping.
+            let samples = (time.as_secs_f64() * samples_per_second) as usize;
+            samples - samples % usize::from(channels)
+        };
+
+        let loop_start = to_samples(self.loop_start);
+        // The loop points can round to the same frame, which would leave nothing to loop.
+        let loop_end = self
+            .loop_end
+            .map(to_samples)
+            .filter(|&end| end > loop_start);
+        let replay = if self.looping && loop_start > 0 {
+            Replay::Recording(Vec::new())
+        } else {
+            Replay::Reopen
+        };
+
+        Ok(MusicSource {
+            music: self.clone(),
+            loop_start,
+            loop_end,
+            decoder,
+            position: 0,
+            replay,
+        })
+    }
+}
+
+/// Where a [`Music`] track is streamed from.
+#[derive(Clone, Debug, PartialEq)]
+enum MusicData {
+    File(PathBuf),
+    Memory(SharedBytes),
+}
+
+/// The bytes of a track in memory, shared between every time it's played.
+#[derive(Clone)]
+struct SharedBytes(Arc<dyn AsRef<[u8]> + Send + Sync>);
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        (*self.0).as_ref()
+    }
+}
+
+impl PartialEq for SharedBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_ref() == other.as_ref()
+    }
+}
+
+impl fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{} bytes>", self.as_ref().len())
+    }
+}
+
+/// Reads a track from a file or from memory.
+enum MusicReader {
+    File(BufReader<File>),
+    Memory(Cursor<SharedBytes>),
+}
+
+impl Read for MusicReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Memory(bytes) => bytes.read(buf),
+        }
+    }
+}
+
+impl Seek for MusicReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::File(file) => file.seek(pos),
+            Self::Memory(bytes) => bytes.seek(pos),
+        }
+    }
+}
+
+/// How a looping [`MusicSource`] gets back to the loop start.
+enum Replay {
+    /// Open the track again. Only used when the loop starts at the beginning, so there's nothing
+    /// to skip.
+    Reopen,
+    /// Keep the samples between the loop points while they're played the first time.
+    Recording(Vec<i16>),
+    /// Play the recorded loop from memory, at `index`.
+    Playing { samples: Vec<i16>, index: usize },
+}
+
+/// Streams a [`Music`] track, jumping back to the loop start at the loop end.
+struct MusicSource {
+    music: Music,
+    decoder: Decoder<MusicReader>,
+    position: usize,
+    loop_start: usize,
+    loop_end: Option<usize>,
+    replay: Replay,
+}
+
+impl MusicSource {
+    /// Start over from the loop start, returning the first sample of the loop, or `None` if
+    /// there's nothing to loop.
+    fn restart(&mut self) -> Option<i16> {
+        match &mut self.replay {
+            Replay::Reopen => {
+                self.decoder = self.music.open().ok()?;
+                self.position = 0;
+                self.decoder.next()
+            }
+            Replay::Recording(samples) => {
+                let samples = mem::take(samples);
+                let first = *samples.first()?;
+                self.replay = Replay::Playing {
+                    index: 1 % samples.len(),
+                    samples,
+                };
+                Some(first)
+            }
+            Replay::Playing { .. } => None,
+        }
+    }
+}
+
+impl Iterator for MusicSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        if let Replay::Playing { samples, index } = &mut self.replay {
+            let sample = *samples.get(*index)?;
+            *index = (*index + 1) % samples.len();
+            return Some(sample);
+        }
+
+        let at_end = self.loop_end.is_some_and(|end| self.position >= end);
+        let sample = if at_end { None } else { self.decoder.next() };
+
+        let sample = match sample {
+            Some(sample) => sample,
+            None if self.music.looping => self.restart()?,
+            None => return None,
+        };
+
+        if let Replay::Recording(samples) = &mut self.replay {
+            if self.position >= self.loop_start {
+                samples.push(sample);
+            }
+        }
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for MusicSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        match self.replay {
+            Replay::Playing { .. } => None,
+            _ => self.decoder.current_frame_len(),
+        }
+    }
+
+    fn channels(&self) -> u16 {
+        self.decoder.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.decoder.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// A fade from one volume to another.
+#[derive(Clone, Copy, Debug)]
+struct Fade {
+    from: f32,
+    to: f32,
+    duration: f64,
+    elapsed: f64,
+}
+
+impl Fade {
+    fn gain(&self) -> f32 {
+        if self.elapsed >= self.duration {
+            return self.to;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let t = (self.elapsed / self.duration) as f32;
+        self.from + (self.to - self.from) * t
+    }
+
+    fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+/// A track that is playing, or fading out.
+struct Channel {
+    music: Music,
+    sink: Sink,
+    gain: f32,
+    fade: Option<Fade>,
+}
+
+impl Channel {
+    fn fade_to(&mut self, to: f32, duration: Duration) {
+        if duration.is_zero() {
+            self.gain = to;
+            self.fade = None;
+        } else {
+            self.fade = Some(Fade {
+                from: self.gain,
+                to,
+                duration: duration.as_secs_f64(),
+                elapsed: 0.0,
+            });
+        }
+    }
+
+    fn advance(&mut self, dt: f64) {
+        if let Some(fade) = &mut self.fade {
+            fade.elapsed += dt;
+            self.gain = fade.gain();
+            if fade.is_done() {
+                self.fade = None;
+            }
+        }
+    }
+}
+
+/// The music channel, with a queue of tracks to play next.
+///
+/// Get this with [`Audio::music()`](super::Audio::music()). Fades and the queue are advanced once
+/// per frame by the game loop.
+pub struct MusicPlayer {
+    handle: Option<OutputStreamHandle>,
+    current: Option<Channel>,
+    fading_out: Vec<Channel>,
+    queue: VecDeque<Music>,
+    volume: f32,
+    bus_gain: f32,
+    paused: bool,
+    last_update: Instant,
+}
+
+impl MusicPlayer {
+    pub(crate) fn new(handle: Option<OutputStreamHandle>) -> Self {
+        Self {
+            handle,
+            current: None,
+            fading_out: Vec::new(),
+            queue: VecDeque::new(),
+            volume: 1.0,
+            bus_gain: 1.0,
+            paused: false,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Start playing `music`, crossfading from whatever was playing over `fade`.
+    ///
+    /// The queue is kept, and continues after this track if it doesn't loop.
+    pub fn play(&mut self, music: &Music, fade: Duration) -> Result<()> {
+        let sink = super::new_sink(self.handle.as_ref());
+        sink.append(music.source()?);
+        if self.paused {
+            sink.pause();
+        }
+
+        if let Some(mut old) = self.current.take() {
+            old.fade_to(0.0, fade);
+            self.fading_out.push(old);
+        }
+
+        let mut channel = Channel {
+            music: music.clone(),
+            sink,
+            gain: 0.0,
+            fade: None,
+        };
+        channel.fade_to(1.0, fade);
+        self.current = Some(channel);
+        self.apply_volume();
+
+        Ok(())
+    }
+
+    /// Add `music` to the end of the queue. If nothing is playing, it starts right away.
+    pub fn queue(&mut self, music: Music) -> Result<()> {
+        self.queue.push_back(music);
+        if self.current.is_none() {
+            self.skip(Duration::ZERO)?;
+        }
+
+        Ok(())
+    }
+
+    /// Crossfade into the next track in the queue over `fade`, or fade out if the queue is empty.
+    pub fn skip(&mut self, fade: Duration) -> Result<()> {
+        if let Some(next) = self.queue.pop_front() {
+            self.play(&next, fade)
+        } else {
+            self.stop(fade);
+            Ok(())
+        }
+    }
+
+    /// Fade out over `fade` and stop. The queue is kept.
+    pub fn stop(&mut self, fade: Duration) {
+        if let Some(mut old) = self.current.take() {
+            old.fade_to(0.0, fade);
+            self.fading_out.push(old);
+        }
+        self.apply_volume();
+    }
+
+    /// Remove every track from the queue, without stopping the current one.
+    pub fn clear_queue(&mut self) {
+        self.queue.clear();
+    }
+
+    /// The tracks waiting to be played, next first.
+    pub fn queued(&self) -> impl Iterator<Item = &Music> {
+        self.queue.iter()
+    }
+
+    /// The track that is playing, not counting any that are fading out.
+    #[must_use]
+    pub fn now_playing(&self) -> Option<&Music> {
+        self.current.as_ref().map(|channel| &channel.music)
+    }
+
+    /// Whether a track is playing, even if paused.
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Pause the music, including any tracks that are fading out.
+    pub fn pause(&mut self) {
+        self.paused = true;
+        for channel in self.channels() {
+            channel.sink.pause();
+        }
+    }
+
+    /// Resume the music after [`Self::pause()`].
+    pub fn resume(&mut self) {
+        self.paused = false;
+        for channel in self.channels() {
+            channel.sink.play();
+        }
+    }
+
+    /// Whether the music is paused.
+    #[must_use]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Set the volume of the music, `1.0` being the tracks' original volume.
+    ///
+    /// This is on top of the volume of the [`Bus::Music`](super::mixer::Bus::Music) bus.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+        self.apply_volume();
+    }
+
+    /// The volume of the music, `1.0` being the tracks' original volume.
+    #[must_use]
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Set the volume of the music bus, from the mixer.
+    pub(crate) fn set_bus_gain(&mut self, gain: f32) {
+        self.bus_gain = gain;
+    }
+
+    /// Advance fades, and start the next queued track when the current one ends.
+    pub(crate) fn update(&mut self) {
+        let now = Instant::now();
+        let dt = if self.paused {
+            0.0
+        } else {
+            (now - self.last_update).as_secs_f64()
+        };
+        self.last_update = now;
+
+        for channel in self.channels() {
+            channel.advance(dt);
+        }
+        self.fading_out
+            .retain(|channel| channel.fade.is_some() && !channel.sink.empty());
+
+        if self.current.as_ref().is_some_and(|c| c.sink.empty()) {
+            self.current = None;
+            // A broken file in the queue shouldn't stop the rest from playing.
+            while !self.queue.is_empty() && self.skip(Duration::ZERO).is_err() {}
+        }
+
+        self.apply_volume();
+    }
+
+    fn channels(&mut self) -> impl Iterator<Item = &mut Channel> {
+        self.current.iter_mut().chain(self.fading_out.iter_mut())
+    }
+
+    fn apply_volume(&mut self) {
+        let volume = self.volume * self.bus_gain;
+        for channel in self.channels() {
+            channel.sink.set_volume(channel.gain * volume);
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Short sounds that are decoded up front, so they can be played instantly and often.
+
+use rodio::{Decoder, Source};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::{CatboxError, Result};
+
+/// A sound effect, fully decoded into memory.
+///
+/// Cloning a `SoundEffect` is cheap, since the samples are shared. Play it with
+/// [`Audio::play()`](crate::audio::Audio::play()).
+///
+/// Long tracks like background music take a lot of memory when decoded, so they are better off
+/// being streamed.
+#[derive(Clone, Debug)]
+pub struct SoundEffect {
+    samples: Arc<[f32]>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl SoundEffect {
+    /// Load and decode a WAV, OGG, MP3 or FLAC file.
+    /// ```no_run
+    /// # use cat_box::audio::sound::SoundEffect;
+    /// let quack = SoundEffect::load("quack.wav").unwrap();
+    /// ```
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = BufReader::new(File::open(path).map_err(|e| CatboxError::asset(path, e))?);
+        Decoder::new(file)
+            .map_err(CatboxError::from)
+            .and_then(Self::decode)
+            .map_err(|e| CatboxError::asset(path, e))
+    }
+
+    /// Decode a WAV, OGG, MP3 or FLAC file from its bytes, like what is returned from
+    /// `include_bytes!`.
+    /// ```no_run
+    /// # use cat_box::audio::sound::SoundEffect;
+    /// let song = SoundEffect::from_bytes(include_bytes!("../../output.mp3")).unwrap();
+    /// ```
+    pub fn from_bytes<B: AsRef<[u8]> + Send + Sync + 'static>(bytes: B) -> Result<Self> {
+        Self::decode(Decoder::new(Cursor::new(bytes))?)
+    }
+
+    fn decode<S: Source<Item = i16>>(source: S) -> Result<Self> {
+        let channels = source.channels();
+        let sample_rate = source.sample_rate();
+        let samples: Arc<[f32]> = source.convert_samples().collect();
+
+        if channels == 0 || sample_rate == 0 {
+            return Err(CatboxError::Audio(
+                "sound has no channels or sample rate".into(),
+            ));
+        }
+
+        Ok(Self {
+            samples,
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// How long the sound lasts when played once.
+    #[must_use]
+    pub fn duration(&self) -> Duration {
+        let frames = self.samples.len() / usize::from(self.channels);
+
+        #[allow(clippy::cast_precision_loss)]
+        Duration::from_secs_f64(frames as f64 / f64::from(self.sample_rate))
+    }
+
+    /// The number of channels, like `2` for stereo.
+    #[must_use]
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// The number of samples per second, per channel.
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// A source playing this sound, which starts over at the end while `looping` is set.
+    pub(crate) fn source(&self, looping: Arc<AtomicBool>) -> SoundSource {
+        SoundSource {
+            sound: self.clone(),
+            position: 0,
+            looping,
+        }
+    }
+}
+
+/// Plays the samples of a [`SoundEffect`] without copying them.
+pub(crate) struct SoundSource {
+    sound: SoundEffect,
+    position: usize,
+    looping: Arc<AtomicBool>,
+}
+
+impl Iterator for SoundSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.sound.samples.len() {
+            if self.sound.samples.is_empty() || !self.looping.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.position = 0;
+        }
+
+        self.position += 1;
+        Some(self.sound.samples[self.position - 1])
+    }
+}
+
+impl Source for SoundSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.sound.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
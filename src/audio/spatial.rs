@@ -0,0 +1,177 @@
+//! Positional sound, which pans and gets quieter with distance from a listener.
+//!
+//! Sounds played with [`Audio::play_at()`](super::Audio::play_at()) have a position in the world.
+//! Each frame, their volume in each ear is worked out from where they are relative to the
+//! [`Listener`], which is usually moved along with the camera or the player.
+//!
+//! ```no_run
+//! # use cat_box::{Game, audio::{spatial::DistanceModel, sound::SoundEffect}, objects::sprite::Sprite};
+//! # let game = Game::new("spatial demo", 1000, 800);
+//! # let player = Sprite::new("duck.png", 500, 400).unwrap();
+//! # let mut enemy = Sprite::new("duck.png", 900, 400).unwrap();
+//! let growl = SoundEffect::load("growl.ogg").unwrap();
+//! let mut growling = None;
+//!
+//! game.run(|ctx| {
+//!     let listener = ctx.audio().listener();
+//!     listener.set_position(player.position());
+//!     listener.set_distance_model(DistanceModel::Linear { reference: 100.0, max: 1500.0 });
+//!
+//!     enemy.translate((-1, 0));
+//!     let playback = growling.get_or_insert_with(|| {
+//!         let playback = ctx.audio().play_at(&growl, enemy.position());
+//!         playback.set_looping(true);
+//!         playback
+//!     });
+//!     playback.set_position(enemy.position());
+//! })
+//! .unwrap();
+//! ```
+
+use crate::math::vec2::Vec2;
+
+/// How a sound gets quieter the further it is from the [`Listener`].
+///
+/// Sounds closer than `reference` are always at full volume.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DistanceModel {
+    /// Distance makes no difference, so sounds are only panned.
+    None,
+    /// Fades out in a straight line, reaching silence at `max`.
+    Linear { reference: f32, max: f32 },
+    /// Halves in volume each time the distance doubles, or faster with a higher `rolloff`.
+    ///
+    /// This is how sound behaves in the real world, with a `rolloff` of `1.0`.
+    Inverse { reference: f32, rolloff: f32 },
+    /// Falls off with the distance to the power of `-rolloff`.
+    Exponential { reference: f32, rolloff: f32 },
+}
+
+impl DistanceModel {
+    /// The volume of a sound `distance` away, from `0.0` to `1.0`.
+    /// ```
+    /// # use cat_box::audio::spatial::DistanceModel;
+    /// let model = DistanceModel::Linear { reference: 100.0, max: 500.0 };
+    /// assert_eq!(model.gain(50.0), 1.0);
+    /// assert_eq!(model.gain(300.0), 0.5);
+    /// assert_eq!(model.gain(1000.0), 0.0);
+    /// ```
+    #[must_use]
+    pub fn gain(&self, distance: f32) -> f32 {
+        let gain = match *self {
+            Self::None => 1.0,
+            Self::Linear { reference, max } => {
+                if distance <= reference {
+                    1.0
+                } else if max <= reference {
+                    0.0
+                } else {
+                    1.0 - (distance - reference) / (max - reference)
+                }
+            }
+            Self::Inverse { reference, rolloff } => {
+                reference / (reference + rolloff * (distance - reference).max(0.0))
+            }
+            Self::Exponential { reference, rolloff } => {
+                (distance.max(reference) / reference).powf(-rolloff)
+            }
+        };
+
+        if gain.is_nan() {
+            1.0
+        } else {
+            gain.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Default for DistanceModel {
+    fn default() -> Self {
+        Self::Inverse {
+            reference: 100.0,
+            rolloff: 1.0,
+        }
+    }
+}
+
+/// Where positional sounds are heard from.
+///
+/// Get this with [`Audio::listener()`](super::Audio::listener()).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Listener {
+    position: Vec2,
+    distance_model: DistanceModel,
+    pan_width: f32,
+}
+
+impl Listener {
+    pub(crate) fn new() -> Self {
+        Self {
+            position: Vec2::default(),
+            distance_model: DistanceModel::default(),
+            pan_width: 500.0,
+        }
+    }
+
+    /// Move the listener, in world coordinates.
+    pub fn set_position<V: Into<Vec2>>(&mut self, position: V) {
+        self.position = position.into();
+    }
+
+    /// Where the listener is, in world coordinates.
+    #[must_use]
+    pub fn position(&self) -> Vec2 {
+        self.position
+    }
+
+    /// Set how sounds get quieter with distance.
+    pub fn set_distance_model(&mut self, model: DistanceModel) {
+        self.distance_model = model;
+    }
+
+    /// How sounds get quieter with distance.
+    #[must_use]
+    pub fn distance_model(&self) -> DistanceModel {
+        self.distance_model
+    }
+
+    /// Set how far to the side a sound has to be to only be heard in one ear. Defaults to `500.0`.
+    pub fn set_pan_width(&mut self, width: f32) {
+        self.pan_width = width;
+    }
+
+    /// How far to the side a sound has to be to only be heard in one ear.
+    #[must_use]
+    pub fn pan_width(&self) -> f32 {
+        self.pan_width
+    }
+
+    /// The volume of a sound at `position` in the left and right ears.
+    /// ```
+    /// # use cat_box::audio::spatial::DistanceModel;
+    /// # use cat_box::audio::Audio;
+    /// let mut audio = Audio::null();
+    /// let listener = audio.listener();
+    /// listener.set_distance_model(DistanceModel::None);
+    ///
+    /// let [left, right] = listener.ear_gains((-1000, 0));
+    /// assert!(left > 0.99 && right < 0.01);
+    /// let [left, right] = listener.ear_gains((0, 0));
+    /// assert!((left - right).abs() < 0.01);
+    /// ```
+    #[must_use]
+    pub fn ear_gains<V: Into<Vec2>>(&self, position: V) -> [f32; 2] {
+        let offset = position.into() - self.position;
+        let gain = self.distance_model.gain(offset.magnitude());
+
+        let pan = if self.pan_width > 0.0 {
+            (offset.x / self.pan_width).clamp(-1.0, 1.0)
+        } else {
+            offset.x.signum()
+        };
+        // Constant power panning, so sounds don't get quieter in the middle
+        let angle = (pan + 1.0) * std::f32::consts::FRAC_PI_4;
+
+        [angle.cos() * gain, angle.sin() * gain]
+    }
+}
@@ -0,0 +1,133 @@
+//! The error type returned by everything in catbox.
+
+use sdl2::{
+    render::{TextureValueError, UpdateTextureError},
+    ttf::{FontError, InitError},
+    video::WindowBuildError,
+    IntegerOrSdlError,
+};
+use std::{
+    error::Error,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Something that went wrong in catbox, or in one of the libraries it uses.
+///
+/// Errors from loading a file are wrapped in [`CatboxError::Asset`], which says which file it was.
+/// ```no_run
+/// # use cat_box::{CatboxError, objects::sprite::Sprite};
+/// # use std::io::ErrorKind;
+/// match Sprite::new("duck.png", 500, 400) {
+///     Ok(duck) => {}
+///     Err(CatboxError::Asset { path, source }) => match *source {
+///         CatboxError::Io(e) if e.kind() == ErrorKind::NotFound => {
+///             println!("{} is missing", path.display());
+///         }
+///         e => println!("{} is broken: {}", path.display(), e),
+///     },
+///     Err(e) => println!("{}", e),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum CatboxError {
+    /// Reading or writing a file failed.
+    Io(std::io::Error),
+    /// SDL failed, like when drawing or creating a texture.
+    Sdl(Box<dyn Error + Send + Sync>),
+    /// `SDL_ttf` failed to start, load a font or render text.
+    Ttf(Box<dyn Error + Send + Sync>),
+    /// An image couldn't be decoded or saved.
+    Image(image::ImageError),
+    /// The audio output couldn't be opened, or a sound couldn't be decoded or played.
+    #[cfg(feature = "audio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+    Audio(Box<dyn Error + Send + Sync>),
+    /// The window couldn't be created.
+    Window(WindowBuildError),
+    /// Loading or saving the file at `path` failed, because of `source`.
+    Asset {
+        path: PathBuf,
+        source: Box<CatboxError>,
+    },
+    /// A config file or string couldn't be parsed.
+    Parse(String),
+    /// Something else went wrong inside catbox.
+    Other(String),
+}
+
+impl CatboxError {
+    /// Wrap `error` with the path of the file that was being loaded.
+    pub(crate) fn asset<P: AsRef<Path>, E: Into<CatboxError>>(path: P, error: E) -> Self {
+        Self::Asset {
+            path: path.as_ref().to_path_buf(),
+            source: Box::new(error.into()),
+        }
+    }
+
+    /// Wrap an error message from SDL, which reports most errors as plain strings.
+    pub(crate) fn sdl(error: String) -> Self {
+        Self::Sdl(error.into())
+    }
+}
+
+impl fmt::Display for CatboxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "I/O error: {e}"),
+            Self::Sdl(e) => write!(f, "SDL error: {e}"),
+            Self::Ttf(e) => write!(f, "font error: {e}"),
+            Self::Image(e) => write!(f, "image error: {e}"),
+            #[cfg(feature = "audio")]
+            Self::Audio(e) => write!(f, "audio error: {e}"),
+            Self::Window(e) => write!(f, "window error: {e}"),
+            Self::Asset { path, source } => {
+                write!(f, "couldn't load `{}`: {source}", path.display())
+            }
+            Self::Parse(e) | Self::Other(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for CatboxError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Sdl(e) | Self::Ttf(e) => Some(e.as_ref()),
+            Self::Image(e) => Some(e),
+            #[cfg(feature = "audio")]
+            Self::Audio(e) => Some(e.as_ref()),
+            Self::Window(e) => Some(e),
+            Self::Asset { source, .. } => Some(source.as_ref()),
+            Self::Parse(_) | Self::Other(_) => None,
+        }
+    }
+}
+
+macro_rules! error_from {
+    ($($variant:ident: $($t:ty),+;)+) => {
+        $($(
+        impl From<$t> for CatboxError {
+            fn from(e: $t) -> Self {
+                CatboxError::$variant(e.into())
+            }
+        }
+        )+)+
+    };
+}
+
+error_from! {
+    Io: std::io::Error;
+    Sdl: IntegerOrSdlError, TextureValueError, UpdateTextureError;
+    Ttf: FontError, InitError;
+    Image: image::ImageError;
+    Window: WindowBuildError;
+}
+
+#[cfg(feature = "audio")]
+error_from! {
+    Audio: rodio::StreamError, rodio::decoder::DecoderError, rodio::PlayError;
+}
+
+pub type Result<T> = std::result::Result<T, CatboxError>;
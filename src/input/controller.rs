@@ -0,0 +1,444 @@
+//! Game controllers, for couch co-op.
+//!
+//! Controllers are opened as they are plugged in, and each one is given the lowest free player
+//! number, starting at `0`. A player keeps their number until their controller is unplugged, so
+//! plugging it back in puts it back in the same slot as long as nobody else took it in the
+//! meantime.
+//!
+//! ```no_run
+//! # use cat_box::{Game, input::controller::Stick, sdl2::controller::Button};
+//! # use std::time::Duration;
+//! # let game = Game::new("controller demo", 1000, 800);
+//! game.run(|ctx| {
+//!     for &player in ctx.controllers().just_connected() {
+//!         println!("Player {} joined", player + 1);
+//!     }
+//!
+//!     for pad in ctx.controllers_mut().iter_mut() {
+//!         let movement = pad.stick(Stick::Left);
+//!         if pad.just_pressed(Button::A) {
+//!             println!("Player {} jumped, moving {:?}", pad.player() + 1, movement);
+//!             pad.rumble(0.5, 0.5, Duration::from_millis(100)).ok();
+//!         }
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! Headless contexts have no controller subsystem. Instead, pushing a
+//! [`ControllerDeviceAdded`](crate::Event::ControllerDeviceAdded) event plugs in a simulated
+//! controller whose id is the event's `which`, and it can then be driven with button and axis
+//! events.
+
+use sdl2::{
+    controller::{Axis, Button, GameController},
+    event::Event,
+    GameControllerSubsystem,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    time::Duration,
+};
+
+use crate::{math::vec2::Vec2, Result};
+
+/// Every controller button, in the order SDL numbers them.
+pub(crate) const BUTTONS: [Button; 21] = [
+    Button::A,
+    Button::B,
+    Button::X,
+    Button::Y,
+    Button::Back,
+    Button::Guide,
+    Button::Start,
+    Button::LeftStick,
+    Button::RightStick,
+    Button::LeftShoulder,
+    Button::RightShoulder,
+    Button::DPadUp,
+    Button::DPadDown,
+    Button::DPadLeft,
+    Button::DPadRight,
+    Button::Misc1,
+    Button::Paddle1,
+    Button::Paddle2,
+    Button::Paddle3,
+    Button::Paddle4,
+    Button::Touchpad,
+];
+
+/// Every controller axis, in the order SDL numbers them.
+pub(crate) const AXES: [Axis; 6] = [
+    Axis::LeftX,
+    Axis::LeftY,
+    Axis::RightX,
+    Axis::RightY,
+    Axis::TriggerLeft,
+    Axis::TriggerRight,
+];
+
+/// One of the two analog sticks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    fn axes(self) -> (Axis, Axis) {
+        match self {
+            Self::Left => (Axis::LeftX, Axis::LeftY),
+            Self::Right => (Axis::RightX, Axis::RightY),
+        }
+    }
+}
+
+/// How far the sticks and triggers have to move before they register, from `0.0` to `1.0`.
+///
+/// Worn sticks rarely rest exactly at the center, so without a deadzone a player would slowly
+/// drift. Past the deadzone, values are rescaled so they still go smoothly from `0.0` to `1.0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deadzones {
+    /// Applied to the distance of each stick from its center, so it is the same in every
+    /// direction.
+    pub stick: f32,
+    /// Applied to each trigger.
+    pub trigger: f32,
+}
+
+impl Default for Deadzones {
+    fn default() -> Self {
+        Self {
+            stick: 0.15,
+            trigger: 0.05,
+        }
+    }
+}
+
+/// A connected game controller, and its state for this frame.
+pub struct Controller {
+    /// `None` for simulated controllers in headless contexts.
+    device: Option<GameController>,
+    id: u32,
+    player: usize,
+    held: HashSet<Button>,
+    pressed: HashSet<Button>,
+    released: HashSet<Button>,
+    axes: HashMap<Axis, i16>,
+    /// The deadzones used by [`Self::axis()`] and [`Self::stick()`].
+    pub deadzones: Deadzones,
+}
+
+impl Controller {
+    fn new(device: Option<GameController>, id: u32, player: usize, deadzones: Deadzones) -> Self {
+        Self {
+            device,
+            id,
+            player,
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            axes: HashMap::new(),
+            deadzones,
+        }
+    }
+
+    /// The player number of this controller, starting at `0`.
+    #[must_use]
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// The joystick instance id SDL uses for this controller in events.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// The name of the controller, like `"Xbox One Controller"`.
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.device
+            .as_ref()
+            .map_or_else(|| "Simulated Controller".to_string(), GameController::name)
+    }
+
+    /// Whether the button is currently held down.
+    #[must_use]
+    pub fn is_pressed(&self, button: Button) -> bool {
+        self.held.contains(&button)
+    }
+
+    /// Whether the button went down since the last update.
+    #[must_use]
+    pub fn just_pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Whether the button was let go since the last update.
+    #[must_use]
+    pub fn just_released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Every button that is currently held down.
+    pub fn held(&self) -> impl Iterator<Item = Button> + '_ {
+        self.held.iter().copied()
+    }
+
+    /// The value of an axis, with the [deadzones](Self::deadzones) applied.
+    ///
+    /// Stick axes go from `-1.0` to `1.0`, with positive y being down. Triggers go from `0.0` to
+    /// `1.0`.
+    #[must_use]
+    pub fn axis(&self, axis: Axis) -> f32 {
+        match axis {
+            Axis::LeftX => self.stick(Stick::Left).x,
+            Axis::LeftY => self.stick(Stick::Left).y,
+            Axis::RightX => self.stick(Stick::Right).x,
+            Axis::RightY => self.stick(Stick::Right).y,
+            Axis::TriggerLeft | Axis::TriggerRight => {
+                let value = self.raw_axis(axis).max(0.0);
+                rescale(value, self.deadzones.trigger)
+            }
+        }
+    }
+
+    /// The position of a stick, with the [deadzones](Self::deadzones) applied.
+    ///
+    /// Each component goes from `-1.0` to `1.0`, and positive y is down, like screen coordinates.
+    #[must_use]
+    pub fn stick(&self, stick: Stick) -> Vec2 {
+        let (x, y) = stick.axes();
+        let raw = Vec2::new(self.raw_axis(x), self.raw_axis(y));
+        let distance = raw.magnitude();
+
+        if distance <= self.deadzones.stick {
+            Vec2::default()
+        } else {
+            raw / distance * rescale(distance.min(1.0), self.deadzones.stick)
+        }
+    }
+
+    /// Rumble the controller for `duration`, with the strength of the low and high frequency
+    /// motors going from `0.0` to `1.0`. This replaces any rumble that is already playing.
+    ///
+    /// Returns an error if the controller can't rumble. Simulated controllers ignore this.
+    pub fn rumble(&mut self, low: f32, high: f32, duration: Duration) -> Result<()> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let strength = |s: f32| (s.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16;
+        let duration = u32::try_from(duration.as_millis()).unwrap_or(u32::MAX);
+
+        match &mut self.device {
+            Some(device) => Ok(device.set_rumble(strength(low), strength(high), duration)?),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop any rumble that is playing.
+    pub fn stop_rumble(&mut self) -> Result<()> {
+        self.rumble(0.0, 0.0, Duration::ZERO)
+    }
+
+    /// The value of an axis from `-1.0` to `1.0`, without any deadzone.
+    fn raw_axis(&self, axis: Axis) -> f32 {
+        let value = self.axes.get(&axis).copied().unwrap_or(0);
+        (f32::from(value) / f32::from(i16::MAX)).max(-1.0)
+    }
+
+    fn consume_edges(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerButtonDown { button, .. } if self.held.insert(button) => {
+                self.pressed.insert(button);
+            }
+            Event::ControllerButtonUp { button, .. } if self.held.remove(&button) => {
+                self.released.insert(button);
+            }
+            Event::ControllerAxisMotion { axis, value, .. } => {
+                self.axes.insert(axis, value);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Map `value` past `deadzone` onto `0.0..=1.0`.
+fn rescale(value: f32, deadzone: f32) -> f32 {
+    if value <= deadzone {
+        0.0
+    } else {
+        ((value - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+/// Every connected game controller.
+///
+/// Get this with [`Context::controllers()`](crate::Context::controllers()).
+pub struct Controllers {
+    subsystem: Option<GameControllerSubsystem>,
+    slots: Vec<Option<Controller>>,
+    connected: Vec<usize>,
+    disconnected: Vec<usize>,
+    deadzones: Deadzones,
+}
+
+impl Controllers {
+    /// Opens every controller that is already plugged in.
+    pub(crate) fn new(subsystem: Option<GameControllerSubsystem>) -> Self {
+        let mut controllers = Self {
+            subsystem,
+            slots: Vec::new(),
+            connected: Vec::new(),
+            disconnected: Vec::new(),
+            deadzones: Deadzones::default(),
+        };
+
+        let count = controllers
+            .subsystem
+            .as_ref()
+            .and_then(|s| s.num_joysticks().ok())
+            .unwrap_or(0);
+        for index in 0..count {
+            controllers.open(index);
+        }
+
+        controllers
+    }
+
+    /// Forget the presses, releases and (dis)connections that an update has seen.
+    pub(crate) fn consume_edges(&mut self) {
+        self.connected.clear();
+        self.disconnected.clear();
+        for pad in self.iter_mut() {
+            pad.consume_edges();
+        }
+    }
+
+    pub(crate) fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => self.open(which),
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if let Some(slot) = self
+                    .slots
+                    .iter_mut()
+                    .find(|slot| matches!(slot, Some(pad) if pad.id == which))
+                {
+                    if let Some(pad) = slot.take() {
+                        self.disconnected.push(pad.player);
+                    }
+                }
+            }
+            Event::ControllerButtonDown { which, .. }
+            | Event::ControllerButtonUp { which, .. }
+            | Event::ControllerAxisMotion { which, .. } => {
+                if let Some(pad) = self.iter_mut().find(|pad| pad.id == which) {
+                    pad.handle(event);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Open the controller at `index`, or plug in a simulated one with that id if there is no
+    /// controller subsystem.
+    fn open(&mut self, index: u32) {
+        let (device, id) = match &self.subsystem {
+            Some(subsystem) => {
+                if !subsystem.is_game_controller(index) {
+                    return;
+                }
+                match subsystem.open(index) {
+                    Ok(device) => {
+                        let id = device.instance_id();
+                        (Some(device), id)
+                    }
+                    Err(_) => return,
+                }
+            }
+            None => (None, index),
+        };
+
+        // SDL also sends an added event for controllers that were opened when starting up.
+        if self.iter().any(|pad| pad.id == id) {
+            return;
+        }
+
+        let player = self
+            .slots
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or_else(|| {
+                self.slots.push(None);
+                self.slots.len() - 1
+            });
+        self.slots[player] = Some(Controller::new(device, id, player, self.deadzones));
+        self.connected.push(player);
+    }
+
+    /// Get the controller for `player`, if one is connected.
+    #[must_use]
+    pub fn get(&self, player: usize) -> Option<&Controller> {
+        self.slots.get(player).and_then(Option::as_ref)
+    }
+
+    /// Get the controller for `player` mutably, for example to make it rumble.
+    pub fn get_mut(&mut self, player: usize) -> Option<&mut Controller> {
+        self.slots.get_mut(player).and_then(Option::as_mut)
+    }
+
+    /// Iterate over every connected controller, in order of player number.
+    pub fn iter(&self) -> impl Iterator<Item = &Controller> {
+        self.slots.iter().flatten()
+    }
+
+    /// Iterate mutably over every connected controller, in order of player number.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Controller> {
+        self.slots.iter_mut().flatten()
+    }
+
+    /// The number of connected controllers.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Whether no controllers are connected.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// The players whose controllers were plugged in since the last update.
+    #[must_use]
+    pub fn just_connected(&self) -> &[usize] {
+        &self.connected
+    }
+
+    /// The players whose controllers were unplugged since the last update.
+    #[must_use]
+    pub fn just_disconnected(&self) -> &[usize] {
+        &self.disconnected
+    }
+
+    /// The deadzones given to newly connected controllers.
+    #[must_use]
+    pub fn deadzones(&self) -> Deadzones {
+        self.deadzones
+    }
+
+    /// Set the deadzones for every connected controller, and any that are connected later.
+    ///
+    /// To change them for one controller, set its [`Controller::deadzones`] instead.
+    pub fn set_deadzones(&mut self, deadzones: Deadzones) {
+        self.deadzones = deadzones;
+        for pad in self.iter_mut() {
+            pad.deadzones = deadzones;
+        }
+    }
+}
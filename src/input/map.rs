@@ -0,0 +1,574 @@
+//! Named actions bound to keys, mouse buttons and game controllers.
+//!
+//! Instead of matching on [`Scancode`](sdl2::keyboard::Scancode)s all over the game, bind them to actions once, and let
+//! players change the bindings through a config file.
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, input::{controller::Stick, map::{Binding, InputMap}}, objects::sprite::Sprite};
+//! # use cat_box::sdl2::controller::Button;
+//! # let game = Game::new("input map demo", 1000, 800);
+//! # let mut player = Sprite::new("duck.png", 500, 400).unwrap();
+//! let mut controls = InputMap::new();
+//! controls.bind("jump", Scancode::Space);
+//! controls.bind("move", Binding::axis_2d(Scancode::W, Scancode::S, Scancode::A, Scancode::D));
+//! controls.bind("move", Binding::axis_2d(Scancode::Up, Scancode::Down, Scancode::Left, Scancode::Right));
+//! controls.bind("move", Stick::Left);
+//! controls.bind("jump", Button::A);
+//!
+//! // Let players override the defaults
+//! if let Ok(saved) = InputMap::load("controls.cfg") {
+//!     controls = saved;
+//! }
+//!
+//! game.run(|ctx| {
+//!     let movement = controls.axis_2d(ctx, "move") * 5.0;
+//!     player.translate(movement.rounded());
+//!
+//!     if controls.just_pressed(ctx, "jump") {
+//!         println!("Jump!");
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! The config file has one action per line, with its bindings separated by commas:
+//!
+//! ```text
+//! # Comments start with a hash
+//! jump = key:Space, mouse:Left, pad:A
+//! move_x = axis(key:A, key:D), padaxis:LeftX
+//! move = axis2d(key:W, key:S, key:A, key:D), axis2d(pad:DPadUp, pad:DPadDown, pad:DPadLeft, pad:DPadRight), stick:Left
+//! ```
+//!
+//! Keys are named like the [`Scancode`](sdl2::keyboard::Scancode) variants, mouse buttons like the [`MouseButton`]
+//! variants, and controller buttons, axes and sticks like the
+//! [`Button`](sdl2::controller::Button), [`Axis`] and [`Stick`] variants.
+//!
+//! By default a map listens to every controller. For couch co-op, give each player their own map
+//! and point it at their controller with [`InputMap::set_controller()`].
+
+use sdl2::{controller::Axis, mouse::MouseButton};
+use std::{collections::BTreeMap, fmt, path::Path, str::FromStr};
+
+use crate::{
+    input::{
+        controller::{Controller, Stick, AXES, BUTTONS},
+        keys::KEYS,
+        InputButton,
+    },
+    math::vec2::Vec2,
+    CatboxError, Context, Result,
+};
+
+/// Something that can trigger an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// A single key, mouse button or controller button.
+    Button(InputButton),
+    /// An axis from `-1.0` to `1.0`, made out of two buttons.
+    Axis {
+        negative: InputButton,
+        positive: InputButton,
+    },
+    /// A 2d axis made out of four buttons, like WASD.
+    ///
+    /// Up is positive y, to match [`Sprite::translate()`](crate::objects::sprite::Sprite::translate()).
+    Axis2D {
+        up: InputButton,
+        down: InputButton,
+        left: InputButton,
+        right: InputButton,
+    },
+    /// An analog axis on a controller.
+    ///
+    /// Up is positive on the stick y axes, like [`Self::Axis2D`].
+    ControllerAxis(Axis),
+    /// An analog stick on a controller, as a 2d axis with up being positive y.
+    Stick(Stick),
+}
+
+impl Binding {
+    /// Creates an axis binding out of two buttons.
+    pub fn axis<N: Into<InputButton>, P: Into<InputButton>>(negative: N, positive: P) -> Self {
+        Self::Axis {
+            negative: negative.into(),
+            positive: positive.into(),
+        }
+    }
+
+    /// Creates a 2d axis binding out of four buttons.
+    pub fn axis_2d<U, D, L, R>(up: U, down: D, left: L, right: R) -> Self
+    where
+        U: Into<InputButton>,
+        D: Into<InputButton>,
+        L: Into<InputButton>,
+        R: Into<InputButton>,
+    {
+        Self::Axis2D {
+            up: up.into(),
+            down: down.into(),
+            left: left.into(),
+            right: right.into(),
+        }
+    }
+
+    fn buttons(&self) -> Vec<InputButton> {
+        match *self {
+            Self::Button(b) => vec![b],
+            Self::Axis { negative, positive } => vec![negative, positive],
+            Self::Axis2D {
+                up,
+                down,
+                left,
+                right,
+            } => vec![up, down, left, right],
+            Self::ControllerAxis(_) | Self::Stick(_) => vec![],
+        }
+    }
+
+    fn value(&self, map: &InputMap, ctx: &Context) -> Vec2 {
+        let get = |b: InputButton| if map.held(ctx, b) { 1.0 } else { 0.0 };
+        // With several controllers, the one pushed the furthest wins.
+        let strongest = |read: &dyn Fn(&Controller) -> Vec2| {
+            map.controllers(ctx)
+                .map(read)
+                .fold(Vec2::default(), |best, v| {
+                    if v.sq_magnitude() > best.sq_magnitude() {
+                        v
+                    } else {
+                        best
+                    }
+                })
+        };
+
+        match *self {
+            Self::Button(b) => Vec2::new(get(b), 0.0),
+            Self::Axis { negative, positive } => Vec2::new(get(positive) - get(negative), 0.0),
+            Self::Axis2D {
+                up,
+                down,
+                left,
+                right,
+            } => Vec2::new(get(right) - get(left), get(up) - get(down)),
+            Self::ControllerAxis(axis) => strongest(&|pad| {
+                let value = pad.axis(axis);
+                match axis {
+                    Axis::LeftY | Axis::RightY => Vec2::new(-value, 0.0),
+                    _ => Vec2::new(value, 0.0),
+                }
+            }),
+            Self::Stick(stick) => strongest(&|pad| {
+                let v = pad.stick(stick);
+                Vec2::new(v.x, -v.y)
+            }),
+        }
+    }
+}
+
+impl<B: Into<InputButton>> From<B> for Binding {
+    fn from(button: B) -> Self {
+        Self::Button(button.into())
+    }
+}
+
+impl From<Axis> for Binding {
+    fn from(axis: Axis) -> Self {
+        Self::ControllerAxis(axis)
+    }
+}
+
+impl From<Stick> for Binding {
+    fn from(stick: Stick) -> Self {
+        Self::Stick(stick)
+    }
+}
+
+impl fmt::Display for InputButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(code) => write!(f, "key:{code:?}"),
+            Self::Mouse(button) => write!(f, "mouse:{button:?}"),
+            Self::Controller(button) => write!(f, "pad:{button:?}"),
+        }
+    }
+}
+
+impl FromStr for InputButton {
+    type Err = CatboxError;
+
+    /// Parse a button written like in the config file, such as `key:LCtrl` or `pad:A`.
+    /// ```
+    /// # use cat_box::{Scancode, input::InputButton, sdl2::mouse::MouseButton};
+    /// assert_eq!("key:LCtrl".parse::<InputButton>().unwrap(), InputButton::Key(Scancode::LCtrl));
+    /// assert_eq!("key:Num1".parse::<InputButton>().unwrap(), InputButton::Key(Scancode::Num1));
+    /// assert_eq!("mouse:Right".parse::<InputButton>().unwrap(), InputButton::Mouse(MouseButton::Right));
+    /// assert!("key:NotAKey".parse::<InputButton>().is_err());
+    /// assert!("Space".parse::<InputButton>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, name) = s.split_once(':').ok_or_else(|| {
+            CatboxError::Parse(format!(
+                "expected `<kind>:<name>`, like `key:Space`, got `{s}`"
+            ))
+        })?;
+        let name = name.trim();
+
+        match kind.trim() {
+            "key" => KEYS
+                .iter()
+                .find(|code| format!("{code:?}") == name)
+                .map(|&code| Self::Key(code))
+                .ok_or_else(|| CatboxError::Parse(format!("unknown key `{name}`"))),
+            "mouse" => match name {
+                "Left" => Ok(Self::Mouse(MouseButton::Left)),
+                "Middle" => Ok(Self::Mouse(MouseButton::Middle)),
+                "Right" => Ok(Self::Mouse(MouseButton::Right)),
+                "X1" => Ok(Self::Mouse(MouseButton::X1)),
+                "X2" => Ok(Self::Mouse(MouseButton::X2)),
+                _ => Err(CatboxError::Parse(format!("unknown mouse button `{name}`"))),
+            },
+            "pad" => BUTTONS
+                .iter()
+                .find(|button| format!("{button:?}") == name)
+                .map(|&button| Self::Controller(button))
+                .ok_or_else(|| CatboxError::Parse(format!("unknown controller button `{name}`"))),
+            kind => Err(CatboxError::Parse(format!("unknown input kind `{kind}`"))),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Button(b) => b.fmt(f),
+            Self::Axis { negative, positive } => write!(f, "axis({negative}, {positive})"),
+            Self::Axis2D {
+                up,
+                down,
+                left,
+                right,
+            } => write!(f, "axis2d({up}, {down}, {left}, {right})"),
+            Self::ControllerAxis(axis) => write!(f, "padaxis:{axis:?}"),
+            Self::Stick(stick) => write!(f, "stick:{stick:?}"),
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = CatboxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let args = |prefix: &str| -> Result<Option<Vec<InputButton>>> {
+            match s.strip_prefix(prefix).and_then(|s| s.strip_suffix(')')) {
+                Some(args) => args
+                    .split(',')
+                    .map(str::parse)
+                    .collect::<Result<_>>()
+                    .map(Some),
+                None => Ok(None),
+            }
+        };
+
+        if let Some(args) = args("axis2d(")? {
+            match args[..] {
+                [up, down, left, right] => Ok(Self::axis_2d(up, down, left, right)),
+                _ => Err(CatboxError::Parse(format!(
+                    "`axis2d` takes 4 buttons, got `{s}`"
+                ))),
+            }
+        } else if let Some(args) = args("axis(")? {
+            match args[..] {
+                [negative, positive] => Ok(Self::axis(negative, positive)),
+                _ => Err(CatboxError::Parse(format!(
+                    "`axis` takes 2 buttons, got `{s}`"
+                ))),
+            }
+        } else if let Some(name) = s.strip_prefix("padaxis:") {
+            let name = name.trim();
+            AXES.iter()
+                .find(|axis| format!("{axis:?}") == name)
+                .map(|&axis| Self::ControllerAxis(axis))
+                .ok_or_else(|| CatboxError::Parse(format!("unknown controller axis `{name}`")))
+        } else if let Some(name) = s.strip_prefix("stick:") {
+            match name.trim() {
+                "Left" => Ok(Self::Stick(Stick::Left)),
+                "Right" => Ok(Self::Stick(Stick::Right)),
+                name => Err(CatboxError::Parse(format!("unknown stick `{name}`"))),
+            }
+        } else {
+            s.parse().map(Self::Button)
+        }
+    }
+}
+
+/// A set of named actions, each with any number of [`Binding`]s.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+    controller: Option<usize>,
+}
+
+impl InputMap {
+    /// Creates a new, empty `InputMap`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load an `InputMap` from a config file. See the [module docs](self) for the format.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        std::fs::read_to_string(path)
+            .map_err(|e| CatboxError::asset(path, e))?
+            .parse()
+            .map_err(|e| CatboxError::asset(path, e))
+    }
+
+    /// Save this `InputMap` to a config file, so it can be loaded with [`Self::load()`].
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_string()).map_err(|e| CatboxError::asset(path, e))
+    }
+
+    /// Add a binding to `action`, keeping any existing ones.
+    /// ```
+    /// # use cat_box::{Scancode, input::map::{Binding, InputMap}, sdl2::mouse::MouseButton};
+    /// let mut controls = InputMap::new();
+    /// controls.bind("fire", Scancode::LCtrl);
+    /// controls.bind("fire", MouseButton::Left);
+    /// controls.bind("steer", Binding::axis(Scancode::A, Scancode::D));
+    /// ```
+    pub fn bind<S: Into<String>, B: Into<Binding>>(&mut self, action: S, binding: B) {
+        self.actions
+            .entry(action.into())
+            .or_default()
+            .push(binding.into());
+    }
+
+    /// Only listen to the controller for `player`, or to every controller if `None`.
+    ///
+    /// This isn't saved with the bindings.
+    /// ```
+    /// # use cat_box::{input::map::InputMap, sdl2::controller::Button};
+    /// let mut player_two = InputMap::new();
+    /// player_two.bind("jump", Button::A);
+    /// player_two.set_controller(Some(1));
+    /// ```
+    pub fn set_controller(&mut self, player: Option<usize>) {
+        self.controller = player;
+    }
+
+    /// The player whose controller this map listens to, or `None` for every controller.
+    #[must_use]
+    pub fn controller(&self) -> Option<usize> {
+        self.controller
+    }
+
+    /// Replace all of `action`'s bindings, for example after the player rebinds it.
+    pub fn set_bindings<S: Into<String>>(&mut self, action: S, bindings: Vec<Binding>) {
+        self.actions.insert(action.into(), bindings);
+    }
+
+    /// Remove `action` and all of its bindings.
+    pub fn unbind(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    /// Get the bindings for `action`.
+    #[must_use]
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterate over every action along with its bindings.
+    pub fn actions(&self) -> impl Iterator<Item = (&str, &[Binding])> {
+        self.actions
+            .iter()
+            .map(|(action, bindings)| (action.as_str(), bindings.as_slice()))
+    }
+
+    /// Whether any of the buttons bound to `action` are held down.
+    #[must_use]
+    pub fn is_pressed(&self, ctx: &Context, action: &str) -> bool {
+        self.buttons(action).any(|b| self.held(ctx, b))
+    }
+
+    /// Whether `action` started being pressed since the last update.
+    ///
+    /// A button that was pressed and let go again in between still counts, so quick taps aren't
+    /// missed.
+    #[must_use]
+    pub fn just_pressed(&self, ctx: &Context, action: &str) -> bool {
+        !self.was_pressed(ctx, action) && self.buttons(action).any(|b| self.edges(ctx, b).0)
+    }
+
+    /// Whether `action` stopped being pressed since the last update.
+    #[must_use]
+    pub fn just_released(&self, ctx: &Context, action: &str) -> bool {
+        !self.is_pressed(ctx, action) && self.buttons(action).any(|b| self.edges(ctx, b).1)
+    }
+
+    /// The value of `action` as a 1d axis, from `-1.0` to `1.0`.
+    ///
+    /// Plain buttons count as `1.0` while held, and 2d axes give their x component.
+    #[must_use]
+    pub fn axis(&self, ctx: &Context, action: &str) -> f32 {
+        self.bindings(action)
+            .iter()
+            .map(|b| b.value(self, ctx).x)
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+
+    /// The value of `action` as a 2d axis, with each component from `-1.0` to `1.0`.
+    ///
+    /// Up is positive y. The vector isn't normalized, so moving diagonally is faster; call
+    /// [`Vec2::normalized()`] on it if that matters.
+    #[must_use]
+    pub fn axis_2d(&self, ctx: &Context, action: &str) -> Vec2 {
+        let sum = self
+            .bindings(action)
+            .iter()
+            .fold(Vec2::default(), |sum, b| sum + b.value(self, ctx));
+
+        Vec2::new(sum.x.clamp(-1.0, 1.0), sum.y.clamp(-1.0, 1.0))
+    }
+
+    fn buttons<'a>(&'a self, action: &str) -> impl Iterator<Item = InputButton> + 'a {
+        self.bindings(action).iter().flat_map(Binding::buttons)
+    }
+
+    /// Whether any of the buttons bound to `action` were held down before their latest presses and releases.
+    fn was_pressed(&self, ctx: &Context, action: &str) -> bool {
+        self.buttons(action).any(|b| self.was_held(ctx, b))
+    }
+
+    /// The controllers this map listens to.
+    fn controllers<'a>(&self, ctx: &'a Context) -> impl Iterator<Item = &'a Controller> {
+        let player = self.controller;
+        ctx.controllers()
+            .iter()
+            .filter(move |pad| player.is_none() || player == Some(pad.player()))
+    }
+
+    /// Whether the button is held down, on any of this map's controllers for controller buttons.
+    fn held(&self, ctx: &Context, button: InputButton) -> bool {
+        match button {
+            InputButton::Controller(button) => {
+                self.controllers(ctx).any(|pad| pad.is_pressed(button))
+            }
+            button => ctx.input().is_pressed(button),
+        }
+    }
+
+    /// Whether the button went down and whether it was let go since the last update, on any of
+    /// this map's controllers for controller buttons.
+    fn edges(&self, ctx: &Context, button: InputButton) -> (bool, bool) {
+        match button {
+            InputButton::Controller(button) => {
+                self.controllers(ctx)
+                    .fold((false, false), |(pressed, released), pad| {
+                        (
+                            pressed || pad.just_pressed(button),
+                            released || pad.just_released(button),
+                        )
+                    })
+            }
+            button => {
+                let input = ctx.input();
+                (input.just_pressed(button), input.just_released(button))
+            }
+        }
+    }
+
+    /// Whether the button was held down before its latest press or release.
+    fn was_held(&self, ctx: &Context, button: InputButton) -> bool {
+        let before = |held: bool, (pressed, released): (bool, bool)| match (pressed, released) {
+            // Going down and back up since the last update means it wasn't held before, and the
+            // other way around means it was.
+            (true, true) => held,
+            (true, false) => false,
+            (false, released) => held || released,
+        };
+
+        match button {
+            InputButton::Controller(b) => self.controllers(ctx).any(|pad| {
+                before(
+                    pad.is_pressed(b),
+                    (pad.just_pressed(b), pad.just_released(b)),
+                )
+            }),
+            button => before(ctx.input().is_pressed(button), self.edges(ctx, button)),
+        }
+    }
+}
+
+impl fmt::Display for InputMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (action, bindings) in &self.actions {
+            let bindings: Vec<String> = bindings.iter().map(Binding::to_string).collect();
+            writeln!(f, "{action} = {}", bindings.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for InputMap {
+    type Err = CatboxError;
+
+    /// Parse an `InputMap` from the config file format described in the [module docs](self).
+    /// ```
+    /// # use cat_box::{Scancode, input::{InputButton, map::{Binding, InputMap}}};
+    /// let controls: InputMap = "jump = key:Space, mouse:Left, pad:A\nmove_x = axis(key:A, key:D), padaxis:LeftX"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(controls.bindings("jump").len(), 3);
+    /// assert_eq!(controls.bindings("jump")[0], Binding::Button(InputButton::Key(Scancode::Space)));
+    /// assert_eq!(controls.bindings("move_x")[0], Binding::axis(Scancode::A, Scancode::D));
+    /// assert!("jump = key:Spacebar".parse::<InputMap>().is_err());
+    /// assert_eq!(controls.to_string().parse::<InputMap>().unwrap(), controls);
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let mut map = Self::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (action, bindings) = line.split_once('=').ok_or_else(|| {
+                CatboxError::Parse(format!("line {}: expected `action = bindings`", i + 1))
+            })?;
+
+            let bindings = split_bindings(bindings)
+                .map(str::parse)
+                .collect::<Result<Vec<Binding>>>()
+                .map_err(|e| CatboxError::Parse(format!("line {}: {}", i + 1, e)))?;
+
+            map.actions
+                .entry(action.trim().to_string())
+                .or_default()
+                .extend(bindings);
+        }
+
+        Ok(map)
+    }
+}
+
+/// Split a comma-separated list of bindings, ignoring the commas inside of `axis(...)`.
+fn split_bindings(s: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0;
+    s.split(move |c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+        c == ',' && depth == 0
+    })
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+}
@@ -0,0 +1,224 @@
+//! Frame-by-frame input tracking.
+//!
+//! [`get_keyboard_state()`](crate::get_keyboard_state()) and
+//! [`get_mouse_state()`](crate::get_mouse_state()) only say what is held down right now. The
+//! [`InputState`] on the [`Context`](crate::Context) also knows what changed since the last update,
+//! so a key press can trigger an action exactly once.
+//!
+//! With a fixed timestep, like in [`Game::run_fixed()`](crate::Game::run_fixed()), presses and
+//! releases are kept until an update tick has seen them. A frame without a tick doesn't lose them,
+//! and a frame with several ticks only reports them on the first.
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, sdl2::mouse::MouseButton};
+//! # let game = Game::new("input demo", 1000, 800);
+//! game.run(|ctx| {
+//!     let input = ctx.input();
+//!
+//!     if input.just_pressed(Scancode::Space) {
+//!         println!("Jump!");
+//!     }
+//!     if input.is_pressed(MouseButton::Left) {
+//!         println!("Dragging by {:?}", input.mouse_delta());
+//!     }
+//! })
+//! .unwrap();
+//! ```
+//!
+//! Game controllers have their own state, see [`controller`]. To bind actions to keys and
+//! controller buttons instead of checking for them directly, see [`map::InputMap`].
+
+pub mod controller;
+mod keys;
+pub mod map;
+
+use sdl2::{
+    controller::Button,
+    event::Event,
+    keyboard::Scancode,
+    mouse::{MouseButton, MouseWheelDirection},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use crate::math::vec2::Vec2Int;
+
+/// A key, mouse button, or controller button.
+///
+/// Anything that takes an `Into<InputButton>` accepts a [`Scancode`], a [`MouseButton`] or a
+/// controller [`Button`] directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InputButton {
+    Key(Scancode),
+    Mouse(MouseButton),
+    /// A button on any game controller. [`InputState`] doesn't track these, since each
+    /// controller has its own state; see [`controller::Controllers`].
+    Controller(Button),
+}
+
+impl From<Scancode> for InputButton {
+    fn from(code: Scancode) -> Self {
+        Self::Key(code)
+    }
+}
+
+impl From<MouseButton> for InputButton {
+    fn from(button: MouseButton) -> Self {
+        Self::Mouse(button)
+    }
+}
+
+impl From<Button> for InputButton {
+    fn from(button: Button) -> Self {
+        Self::Controller(button)
+    }
+}
+
+/// The state of the keyboard and mouse, updated once per frame.
+///
+/// Get this with [`Context::input()`](crate::Context::input()).
+pub struct InputState {
+    frame_time: Instant,
+    held: HashMap<InputButton, Instant>,
+    pressed: HashSet<InputButton>,
+    released: HashSet<InputButton>,
+    mouse: Vec2Int,
+    mouse_delta: Vec2Int,
+    wheel: Vec2Int,
+}
+
+impl InputState {
+    pub(crate) fn new() -> Self {
+        Self {
+            frame_time: Instant::now(),
+            held: HashMap::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            mouse: Vec2Int::default(),
+            mouse_delta: Vec2Int::default(),
+            wheel: Vec2Int::default(),
+        }
+    }
+
+    /// Forget how the mouse moved last frame. Called before the new frame's events are handled.
+    pub(crate) fn begin_frame(&mut self) {
+        self.frame_time = Instant::now();
+        self.mouse_delta = Vec2Int::default();
+        self.wheel = Vec2Int::default();
+    }
+
+    /// Forget the presses and releases that an update has seen.
+    pub(crate) fn consume_edges(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+    }
+
+    pub(crate) fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::KeyDown {
+                scancode: Some(code),
+                repeat: false,
+                ..
+            } => self.press(code.into()),
+            Event::KeyUp {
+                scancode: Some(code),
+                ..
+            } => self.release(code.into()),
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse = Vec2Int::new(x, y);
+                self.press(mouse_btn.into());
+            }
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse = Vec2Int::new(x, y);
+                self.release(mouse_btn.into());
+            }
+            Event::MouseMotion {
+                x, y, xrel, yrel, ..
+            } => {
+                self.mouse = Vec2Int::new(x, y);
+                self.mouse_delta += Vec2Int::new(xrel, yrel);
+            }
+            Event::MouseWheel {
+                x, y, direction, ..
+            } => {
+                let scroll = Vec2Int::new(x, y);
+                self.wheel += match direction {
+                    MouseWheelDirection::Flipped => -scroll,
+                    _ => scroll,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    fn press(&mut self, button: InputButton) {
+        if !self.held.contains_key(&button) {
+            self.held.insert(button, self.frame_time);
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: InputButton) {
+        if self.held.remove(&button).is_some() {
+            self.released.insert(button);
+        }
+    }
+
+    /// Whether the key or mouse button is currently held down.
+    #[must_use]
+    pub fn is_pressed<B: Into<InputButton>>(&self, button: B) -> bool {
+        self.held.contains_key(&button.into())
+    }
+
+    /// Whether the key or mouse button went down since the last update.
+    #[must_use]
+    pub fn just_pressed<B: Into<InputButton>>(&self, button: B) -> bool {
+        self.pressed.contains(&button.into())
+    }
+
+    /// Whether the key or mouse button was let go since the last update.
+    #[must_use]
+    pub fn just_released<B: Into<InputButton>>(&self, button: B) -> bool {
+        self.released.contains(&button.into())
+    }
+
+    /// How long the key or mouse button has been held down, as of the start of this frame.
+    ///
+    /// Returns `None` if it isn't held.
+    #[must_use]
+    pub fn held_for<B: Into<InputButton>>(&self, button: B) -> Option<Duration> {
+        self.held
+            .get(&button.into())
+            .map(|&since| self.frame_time - since)
+    }
+
+    /// Every key and mouse button that is currently held down.
+    pub fn held(&self) -> impl Iterator<Item = InputButton> + '_ {
+        self.held.keys().copied()
+    }
+
+    /// The position of the mouse, in screen coordinates.
+    #[must_use]
+    pub fn mouse_position(&self) -> Vec2Int {
+        self.mouse
+    }
+
+    /// How far the mouse moved this frame.
+    #[must_use]
+    pub fn mouse_delta(&self) -> Vec2Int {
+        self.mouse_delta
+    }
+
+    /// How far the mouse wheel scrolled this frame. Positive y is away from the user, positive x
+    /// is to the right.
+    #[must_use]
+    pub fn wheel(&self) -> Vec2Int {
+        self.wheel
+    }
+}
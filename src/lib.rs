@@ -92,25 +92,53 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "audio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+pub mod audio;
+mod error;
+pub mod input;
 pub mod math;
 pub mod objects;
+pub mod scene;
+pub mod snapshot;
+pub mod text;
+mod texture;
 
+pub use error::{CatboxError, Result};
 pub use objects::physics::*;
 use sdl2::VideoSubsystem;
 
 #[cfg(feature = "audio")]
 use rodio::{self, source::Source, Decoder, OutputStream};
 use sdl2::{
+    controller::{Axis, Button},
+    hint::{self, Hint},
     mouse::MouseButton,
+    pixels::PixelFormatEnum,
     rect::Rect,
-    render::{Canvas, TextureCreator, TextureValueError},
-    ttf::{FontError, InitError, Sdl2TtfContext},
-    video::{Window, WindowBuildError, WindowContext},
-    EventPump, IntegerOrSdlError,
+    render::{Canvas, TextureCreator},
+    ttf::Sdl2TtfContext,
+    video::{Window, WindowContext},
+    EventPump, GameControllerSubsystem,
+};
+use std::{
+    cell::Cell,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Instant,
 };
-use std::{cell::Cell, path::Path, time::Instant};
 
-use math::vec2::Vec2Int;
+use input::{
+    controller::{Controllers, Stick},
+    InputState,
+};
+use math::vec2::{Vec2, Vec2Int};
+use objects::camera::Camera2D;
+use objects::timer::{FixedClock, Timestep};
+use scene::{Scene, SceneStack};
+use text::{Align, Anchor, BitmapFont, FontCache, RichText, TextLayout};
+use texture::{SharedTexture, TextureCache};
 #[doc(no_inline)]
 pub use sdl2::{self, event::Event, keyboard::Scancode, pixels::Color};
 
@@ -129,50 +157,6 @@ macro_rules! cloned {
     }
 }
 
-macro_rules! error_from_format {
-    ($($t:ty),+) => {
-        $(
-        impl From<$t> for CatboxError {
-            fn from(e: $t) -> Self {
-                CatboxError(format!("{}", e))
-            }
-        }
-        )+
-    };
-}
-#[derive(Clone, Debug)]
-pub struct CatboxError(String);
-
-impl From<String> for CatboxError {
-    fn from(e: String) -> Self {
-        CatboxError(e)
-    }
-}
-
-error_from_format! {
-    WindowBuildError,
-    IntegerOrSdlError,
-    TextureValueError,
-    FontError,
-    InitError
-}
-
-#[cfg(feature = "audio")]
-error_from_format! {
-    rodio::StreamError,
-    std::io::Error,
-    rodio::decoder::DecoderError,
-    rodio::PlayError
-}
-
-impl std::fmt::Display for CatboxError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.0.fmt(f)
-    }
-}
-
-pub type Result<T> = std::result::Result<T, CatboxError>;
-
 /// Wrapper type around SDL's [`EventPump`](sdl2::EventPump). See those docs for more info.
 pub struct Events {
     pump: EventPump,
@@ -205,18 +189,84 @@ pub struct Context {
     canvas: Canvas<Window>,
     event_pump: EventPump,
     texture_creator: TextureCreator<WindowContext>,
+    // Fonts have to be closed before the TTF context is dropped.
+    fonts: FontCache,
     ttf_subsystem: Sdl2TtfContext,
+    textures: TextureCache,
+    scripted: VecDeque<Event>,
+    simulated: Option<SimulatedInput>,
+    camera: Option<Camera2D>,
+    input: InputState,
+    controllers: Controllers,
+    #[cfg(feature = "audio")]
+    audio: Option<audio::Audio>,
 }
 
 impl Context {
-    fn new(canvas: Canvas<Window>, pump: EventPump, ttf_subsystem: Sdl2TtfContext) -> Self {
+    fn new(
+        canvas: Canvas<Window>,
+        pump: EventPump,
+        ttf_subsystem: Sdl2TtfContext,
+        controllers: Option<GameControllerSubsystem>,
+    ) -> Self {
         let creator = canvas.texture_creator();
         Self {
             canvas,
             event_pump: pump,
             texture_creator: creator,
+            fonts: FontCache::default(),
             ttf_subsystem,
+            textures: TextureCache::default(),
+            scripted: VecDeque::new(),
+            simulated: None,
+            camera: None,
+            input: InputState::new(),
+            controllers: Controllers::new(controllers),
+            #[cfg(feature = "audio")]
+            audio: None,
+        }
+    }
+
+    /// Create a context that renders offscreen, without opening a window.
+    ///
+    /// This uses SDL's dummy video driver and software renderer, so it works on machines without a
+    /// display, like CI runners. Keyboard and mouse state come from [`Self::push_event()`] instead
+    /// of the real devices, and whatever has been drawn can be read back with [`Self::pixels()`].
+    ///
+    /// SDL can only be initialized once at a time, so tests using this must not run in parallel.
+    /// The dummy video driver also stays selected for the rest of the process.
+    ///
+    /// ```
+    /// # use cat_box::Context;
+    /// let ctx = Context::headless(64, 64).unwrap();
+    /// let pixels = ctx.pixels().unwrap();
+    /// assert_eq!(pixels.len(), 64 * 64 * 4);
+    /// ```
+    pub fn headless(width: u32, height: u32) -> Result<Self> {
+        hint::set_with_priority("SDL_VIDEODRIVER", "dummy", &Hint::Override);
+
+        let sdl_context = sdl2::init().map_err(CatboxError::sdl)?;
+        let video_subsystem = sdl_context.video().map_err(CatboxError::sdl)?;
+
+        let window = video_subsystem
+            .window("cat-box", width, height)
+            .hidden()
+            .build()?;
+
+        let canvas = window.into_canvas().software().build()?;
+        let s = sdl2::ttf::init()?;
+
+        let event_pump = sdl_context.event_pump().map_err(CatboxError::sdl)?;
+
+        let mut ctx = Self::new(canvas, event_pump, s, None);
+        ctx.simulated = Some(SimulatedInput::default());
+        #[cfg(feature = "audio")]
+        {
+            ctx.audio = Some(audio::Audio::null());
         }
+        ctx.clear();
+
+        Ok(ctx)
     }
 
     /// Get the inner [`Canvas`](sdl2::render::Canvas) and [`TextureCreator`](sdl2::render::TextureCreator).
@@ -236,6 +286,20 @@ impl Context {
         )
     }
 
+    /// Get the texture for the image with the given `key`, reusing `cached` if it still belongs
+    /// to this context's renderer.
+    pub(crate) fn texture(
+        &mut self,
+        key: u64,
+        surf: &sdl2::surface::SurfaceRef,
+        cached: Option<&Rc<SharedTexture>>,
+    ) -> Result<Rc<SharedTexture>> {
+        match cached {
+            Some(texture) if texture.belongs_to(&self.canvas) => Ok(Rc::clone(texture)),
+            _ => self.textures.get_or_create(key, surf, &self.canvas),
+        }
+    }
+
     fn update(&mut self) {
         self.canvas.present();
     }
@@ -244,16 +308,210 @@ impl Context {
         self.canvas.clear();
     }
 
-    fn check_for_quit(&mut self) -> bool {
-        let (_, _, pump) = self.inner();
+    /// Queue an event as if it came from SDL. It is handled at the start of the next frame.
+    ///
+    /// In a [headless](Self::headless()) context, keyboard and mouse events also update what
+    /// [`get_keyboard_state()`] and [`get_mouse_state()`] return, straight away.
+    pub fn push_event(&mut self, event: Event) {
+        if let Some(simulated) = &mut self.simulated {
+            simulated.handle(&event);
+        }
+
+        self.scripted.push_back(event);
+    }
+
+    /// Read back everything drawn so far, as tightly packed RGBA bytes in row-major order.
+    ///
+    /// The image is [`Self::output_size()`] pixels large.
+    pub fn pixels(&self) -> Result<Vec<u8>> {
+        self.canvas
+            .read_pixels(None, PixelFormatEnum::RGBA32)
+            .map_err(CatboxError::sdl)
+    }
+
+    /// Capture everything drawn so far as an image.
+    ///
+    /// Useful for saving reference images for [`snapshot::compare_snapshot()`].
+    pub fn capture(&self) -> Result<image::RgbaImage> {
+        let (width, height) = self.output_size()?;
+
+        image::RgbaImage::from_raw(width, height, self.pixels()?).ok_or_else(|| {
+            CatboxError::Other("pixel buffer doesn't match the output size".to_string())
+        })
+    }
+
+    /// Get the size of the area being drawn to, in pixels.
+    pub fn output_size(&self) -> Result<(u32, u32)> {
+        self.canvas.output_size().map_err(CatboxError::sdl)
+    }
+
+    /// Set the camera that sprites and text are drawn through, or `None` to draw in screen space.
+    ///
+    /// See [`Camera2D`] for more info.
+    pub fn set_camera(&mut self, camera: Option<Camera2D>) {
+        self.camera = camera;
+        self.canvas
+            .set_clip_rect(camera.and_then(|camera| camera.viewport));
+    }
+
+    /// Get the current camera, if there is one.
+    #[must_use]
+    pub fn camera(&self) -> Option<&Camera2D> {
+        self.camera.as_ref()
+    }
+
+    /// Get the current camera mutably, if there is one.
+    pub fn camera_mut(&mut self) -> Option<&mut Camera2D> {
+        self.camera.as_mut()
+    }
+
+    /// Convert a point in the world to a point on the screen, using the current camera.
+    ///
+    /// Without a camera, the point is returned unchanged.
+    pub fn world_to_screen<V: Into<Vec2>>(&self, point: V) -> Vec2 {
+        match self.camera {
+            Some(camera) => camera.world_to_screen(point, self.screen_size()),
+            None => point.into(),
+        }
+    }
 
-        for event in pump.poll_iter() {
-            if let Event::Quit { .. } = event {
-                return true;
+    /// Convert a point on the screen to a point in the world, using the current camera.
+    ///
+    /// Without a camera, the point is returned unchanged.
+    /// ```no_run
+    /// # use cat_box::*;
+    /// # let game = Game::new("catbox-demo", 10, 10);
+    /// # game.run(|ctx| {
+    /// let m = get_mouse_state(ctx);
+    /// let (x, y) = ctx.screen_to_world((m.x, m.y)).into();
+    /// # });
+    /// ```
+    pub fn screen_to_world<V: Into<Vec2>>(&self, point: V) -> Vec2 {
+        match self.camera {
+            Some(camera) => camera.screen_to_world(point, self.screen_size()),
+            None => point.into(),
+        }
+    }
+
+    /// Work out where a rect in the world, and its angle, should be drawn on the screen.
+    pub(crate) fn project(&mut self, rect: Rect, angle: f64) -> (Rect, f64) {
+        match self.camera {
+            Some(camera) => {
+                self.canvas.set_clip_rect(camera.viewport);
+                camera.project(rect, angle, self.screen_size())
             }
+            None => (rect, angle),
+        }
+    }
+
+    fn screen_size(&self) -> (u32, u32) {
+        self.canvas.output_size().unwrap_or_default()
+    }
+
+    /// Get the keyboard and mouse state for this frame, including what changed since the last update.
+    ///
+    /// See [`InputState`] for more info.
+    #[must_use]
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    /// Get every connected game controller, along with their state for this frame.
+    ///
+    /// See [`Controllers`] for more info.
+    #[must_use]
+    pub fn controllers(&self) -> &Controllers {
+        &self.controllers
+    }
+
+    /// Get every connected game controller mutably, to make them rumble or change their
+    /// deadzones.
+    pub fn controllers_mut(&mut self) -> &mut Controllers {
+        &mut self.controllers
+    }
+
+    /// Use a [bitmap font](text::BitmapFont) wherever `name` is given as the font, in
+    /// [`draw_text()`] and [`TextLayout`].
+    ///
+    /// `.fnt` files don't need to be added, they're loaded by their path automatically.
+    /// ```no_run
+    /// # use cat_box::{*, text::BitmapFont};
+    /// # let game = Game::new("", 1000, 800);
+    /// let mut font = Some(BitmapFont::grid("font.png", 8, 8, "ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap());
+    /// game.run(|ctx| {
+    ///     // Only add the font on the first frame
+    ///     if let Some(font) = font.take() {
+    ///         ctx.add_bitmap_font("pixels", font);
+    ///     }
+    ///
+    ///     draw_text(ctx, "HELLO", "pixels", 16, (500, 400), TextMode::Transparent { colour: (255, 255, 255) }).unwrap();
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn add_bitmap_font<P: Into<PathBuf>>(&mut self, name: P, font: BitmapFont) {
+        self.fonts.add_bitmap(name.into(), font);
+    }
+
+    /// Load a font in the given size ahead of time, so the first [`draw_text()`] with it doesn't
+    /// have to.
+    pub fn preload_font<P: AsRef<Path>>(&mut self, font: P, size: u16) -> Result<()> {
+        self.fonts
+            .get(&self.ttf_subsystem, font.as_ref(), size)
+            .map(|_| ())
+    }
+
+    /// The width and height of `text` when drawn with [`draw_text()`], before any camera zoom.
+    ///
+    /// See [`measure_text()`] for text with a [`TextLayout`].
+    pub fn text_size<S: AsRef<str>, P: AsRef<Path>>(
+        &mut self,
+        text: S,
+        font: P,
+        size: u16,
+    ) -> Result<(u32, u32)> {
+        measure_text(self, text, &TextLayout::new(font.as_ref(), size))
+    }
+
+    /// Get the audio output, opening it if this is the first time it's used.
+    ///
+    /// Headless contexts always use a [null output](audio::Audio::null()). See [`audio`] for more
+    /// info.
+    #[cfg(feature = "audio")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
+    pub fn audio(&mut self) -> &mut audio::Audio {
+        self.audio.get_or_insert_with(audio::Audio::new)
+    }
+
+    /// Take every event that happened since the last frame, scripted ones first, and update the
+    /// input state with them. This must be called exactly once per frame.
+    fn poll_events(&mut self) -> Vec<Event> {
+        let mut events: Vec<Event> = self.scripted.drain(..).collect();
+        events.extend(self.event_pump.poll_iter());
+
+        self.input.begin_frame();
+        for event in &events {
+            self.input.handle(event);
+            self.controllers.handle(event);
+        }
+        #[cfg(feature = "audio")]
+        if let Some(audio) = &mut self.audio {
+            audio.update();
         }
 
-        false
+        events
+    }
+
+    /// Forget the key, button and controller presses and releases, once an update has had a
+    /// chance to see them.
+    fn consume_edges(&mut self) {
+        self.input.consume_edges();
+        self.controllers.consume_edges();
+    }
+
+    fn check_for_quit(&mut self) -> bool {
+        self.poll_events()
+            .iter()
+            .any(|event| matches!(event, Event::Quit { .. }))
     }
 
     /// Set the background colour. See [`Canvas::set_draw_color()`](sdl2::render::Canvas::set_draw_color()) for more info.
@@ -276,11 +534,27 @@ pub enum TextMode {
     },
 }
 
+impl TextMode {
+    fn colours(self) -> (Color, Option<Color>) {
+        match self {
+            TextMode::Transparent { colour: (r, g, b) } => (Color::RGB(r, g, b), None),
+            TextMode::Shaded {
+                foreground: (fr, fg, fb),
+                background: (br, bg, bb),
+            } => (Color::RGB(fr, fg, fb), Some(Color::RGB(br, bg, bb))),
+        }
+    }
+}
+
 /// Draw text to the screen.
 ///
-/// This loads a font from the current directory, case sensitive.
+/// This loads a font from the current directory, case sensitive. Each font is only loaded once
+/// per size, and each character is only rendered the first time it's drawn, so drawing the same
+/// text every frame is cheap. `font` can also be a [bitmap font](text::BitmapFont).
 ///
-/// `pos` refers to the *center* of the rendered text.
+/// `pos` refers to the *center* of the rendered text. If a [camera](Context::set_camera()) is
+/// set, it is a position in the world. Each `\n` starts a new, centered line; use
+/// [`draw_text_layout()`] for wrapping and other alignments.
 ///
 /// Refer to [`TextMode`] for information about colouring.
 ///
@@ -302,29 +576,124 @@ pub fn draw_text<S: AsRef<str>, I: Into<Vec2Int>>(
     pos: I,
     mode: TextMode,
 ) -> Result<()> {
-    let font = ctx.ttf_subsystem.load_font(font, size)?;
-    let renderer = font.render(text.as_ref());
-
-    let surf = match mode {
-        TextMode::Transparent { colour: (r, g, b) } => renderer.solid(Color::RGB(r, g, b)),
-        TextMode::Shaded {
-            foreground: (fr, fg, fb),
-            background: (br, bg, bb),
-        } => renderer.shaded(Color::RGB(fr, fg, fb), Color::RGB(br, bg, bb)),
-    }?;
+    let layout = TextLayout::new(font, size)
+        .with_align(Align::Center)
+        .with_anchor(Anchor::Center);
+    draw_text_layout(ctx, text, pos, &layout, mode)
+}
 
-    drop(font);
-    let (creator, canvas, _) = ctx.inner();
-    let texture = creator.create_texture_from_surface(&surf)?;
+/// Draw text to the screen, broken into lines and aligned as described by `layout`.
+///
+/// `pos` is where the layout's [anchor](TextLayout::with_anchor()) goes. If a
+/// [camera](Context::set_camera()) is set, it is a position in the world. With
+/// [`TextMode::Shaded`], the background is drawn behind each line.
+/// ```no_run
+/// # use cat_box::{*, text::{Anchor, TextLayout}};
+/// # let game = Game::new("", 1000, 800);
+/// # game.run(|ctx| {
+/// let layout = TextLayout::new("arial.ttf", 24)
+///     .with_wrap_width(300)
+///     .with_anchor(Anchor::BottomLeft);
+/// let mode = TextMode::Transparent { colour: (255, 255, 255) };
+/// draw_text_layout(ctx, "A long line of text that needs wrapping", (20, 780), &layout, mode)
+///     .unwrap();
+/// # });
+/// ```
+pub fn draw_text_layout<S: AsRef<str>, I: Into<Vec2Int>>(
+    ctx: &mut Context,
+    text: S,
+    pos: I,
+    layout: &TextLayout,
+    mode: TextMode,
+) -> Result<()> {
+    let text = text.as_ref();
+    if text.is_empty() {
+        return Ok(());
+    }
 
     let pos = pos.into();
+    let font = ctx
+        .fonts
+        .get(&ctx.ttf_subsystem, layout.font(), layout.size())?;
+    let height = font.height();
+    let lines = layout.lay_out(font, text);
+
+    let lines: Vec<_> = lines
+        .into_iter()
+        .filter(|line| !line.range.is_empty())
+        .map(|line| {
+            let dest_rect = Rect::new(pos.x + line.x, pos.y + line.y, line.width, height);
+            (&text[line.range], ctx.project(dest_rect, 0.0))
+        })
+        .collect();
+
+    let (colour, background) = mode.colours();
+    let font = ctx
+        .fonts
+        .get(&ctx.ttf_subsystem, layout.font(), layout.size())?;
+    for (text, (dest_rect, angle)) in lines {
+        font.draw(&mut ctx.canvas, text, dest_rect, angle, colour, background)?;
+    }
 
-    let srect = surf.rect();
-    let dest_rect: Rect = Rect::from_center((pos.x, pos.y), srect.width(), srect.height());
+    Ok(())
+}
 
-    canvas.copy_ex(&texture, None, dest_rect, 0.0, None, false, false)?;
+/// Draw [rich text](text::RichText) to the screen, laid out by `layout`.
+///
+/// `mode` is used for the parts of the text without a colour, and for the background. Otherwise,
+/// this works like [`draw_text_layout()`]. Bold and italic text is drawn by slanting or
+/// thickening the font given by `layout`.
+/// ```no_run
+/// # use cat_box::{*, text::{RichText, TextLayout}};
+/// # let game = Game::new("", 1000, 800);
+/// let mut text = RichText::new("[color=red]Danger![/color] [i]Don't touch.[/i]").unwrap();
+/// let layout = TextLayout::new("arial.ttf", 24);
+/// # game.run(|ctx| {
+/// let mode = TextMode::Transparent { colour: (255, 255, 255) };
+/// draw_rich_text(ctx, &mut text, (20, 20), &layout, mode).unwrap();
+/// # });
+/// ```
+pub fn draw_rich_text<I: Into<Vec2Int>>(
+    ctx: &mut Context,
+    text: &mut RichText,
+    pos: I,
+    layout: &TextLayout,
+    mode: TextMode,
+) -> Result<()> {
+    text.draw(ctx, pos.into(), layout, mode)
+}
 
-    Ok(())
+/// The width and height of [rich text](text::RichText) when drawn with [`draw_rich_text()`],
+/// without drawing it.
+pub fn measure_rich_text(
+    ctx: &mut Context,
+    text: &RichText,
+    layout: &TextLayout,
+) -> Result<(u32, u32)> {
+    text.measure(ctx, layout)
+}
+
+/// The width and height of `text` when drawn with [`draw_text_layout()`], without drawing it.
+///
+/// The width is that of the widest line, which can be less than the wrap width. Neither are
+/// affected by the camera.
+/// ```no_run
+/// # use cat_box::{*, text::TextLayout};
+/// # let game = Game::new("", 1000, 800);
+/// # game.run(|ctx| {
+/// let layout = TextLayout::new("arial.ttf", 24).with_wrap_width(300);
+/// let (width, height) = measure_text(ctx, "Some text for a dialog box", &layout).unwrap();
+/// # });
+/// ```
+pub fn measure_text<S: AsRef<str>>(
+    ctx: &mut Context,
+    text: S,
+    layout: &TextLayout,
+) -> Result<(u32, u32)> {
+    let font = ctx
+        .fonts
+        .get(&ctx.ttf_subsystem, layout.font(), layout.size())?;
+    Ok(layout.measure(font, text.as_ref()))
 }
 
 /// Representation of the mouse state.
@@ -370,6 +739,61 @@ pub struct KeyboardRepr {
     pub keys: Vec<Scancode>,
 }
 
+/// Representation of a game controller's state.
+pub struct ControllerRepr {
+    pub player: usize,
+    pub buttons: Vec<Button>,
+    pub left_stick: Vec2,
+    pub right_stick: Vec2,
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+}
+
+/// Keyboard and mouse state built up from scripted events, for headless contexts.
+#[derive(Default)]
+struct SimulatedInput {
+    keys: Vec<Scancode>,
+    buttons: Vec<MouseButton>,
+    x: i32,
+    y: i32,
+}
+
+impl SimulatedInput {
+    fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::KeyDown {
+                scancode: Some(code),
+                ..
+            } if !self.keys.contains(&code) => self.keys.push(code),
+            Event::KeyUp {
+                scancode: Some(code),
+                ..
+            } => self.keys.retain(|&k| k != code),
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                if !self.buttons.contains(&mouse_btn) {
+                    self.buttons.push(mouse_btn);
+                }
+                self.x = x;
+                self.y = y;
+            }
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                self.buttons.retain(|&b| b != mouse_btn);
+                self.x = x;
+                self.y = y;
+            }
+            Event::MouseMotion { x, y, .. } => {
+                self.x = x;
+                self.y = y;
+            }
+            _ => {}
+        }
+    }
+}
+
 /// Get the mouse state.
 /// ```no_run
 /// # use cat_box::*;
@@ -379,6 +803,14 @@ pub struct KeyboardRepr {
 /// println!("({}, {})", m.x, m.y);
 /// # });
 pub fn get_mouse_state(ctx: &mut Context) -> MouseRepr {
+    if let Some(simulated) = &ctx.simulated {
+        return MouseRepr {
+            buttons: simulated.buttons.clone(),
+            x: simulated.x,
+            y: simulated.y,
+        };
+    }
+
     let (_, _, pump) = ctx.inner();
 
     let mouse = pump.mouse_state();
@@ -401,6 +833,12 @@ pub fn get_mouse_state(ctx: &mut Context) -> MouseRepr {
 /// }
 /// # });
 pub fn get_keyboard_state(ctx: &mut Context) -> KeyboardRepr {
+    if let Some(simulated) = &ctx.simulated {
+        return KeyboardRepr {
+            keys: simulated.keys.clone(),
+        };
+    }
+
     let (_, _, pump) = ctx.inner();
 
     let keyboard = pump.keyboard_state();
@@ -410,6 +848,33 @@ pub fn get_keyboard_state(ctx: &mut Context) -> KeyboardRepr {
     }
 }
 
+/// Get the state of the controller for `player`, or `None` if it isn't connected.
+///
+/// Sticks and triggers have the controller's [deadzones](input::controller::Deadzones) applied,
+/// and positive y is down on both sticks.
+/// ```no_run
+/// # use cat_box::*;
+/// # let game = Game::new("catbox-demo", 10, 10);
+/// # game.run(|ctx| {
+/// if let Some(pad) = get_controller_state(ctx, 0) {
+///     println!("Player 1 is holding {:?}", pad.buttons);
+///     println!("Left stick: {:?}", pad.left_stick);
+/// }
+/// # });
+#[must_use]
+pub fn get_controller_state(ctx: &Context, player: usize) -> Option<ControllerRepr> {
+    let pad = ctx.controllers.get(player)?;
+
+    Some(ControllerRepr {
+        player,
+        buttons: pad.held().collect(),
+        left_stick: pad.stick(Stick::Left),
+        right_stick: pad.stick(Stick::Right),
+        left_trigger: pad.axis(Axis::TriggerLeft),
+        right_trigger: pad.axis(Axis::TriggerRight),
+    })
+}
+
 /// Representation of the game.
 pub struct Game {
     /// The title that the window displays.
@@ -476,8 +941,189 @@ impl Game {
     /// });
     /// ```
     pub fn run<F: FnMut(&mut Context)>(&self, mut func: F) -> Result<()> {
-        let sdl_context = sdl2::init()?;
-        let video_subsystem = sdl_context.video()?;
+        let mut ctx = self.open_context()?;
+
+        loop {
+            if self.stopped.get() || ctx.check_for_quit() {
+                break;
+            }
+            ctx.clear();
+            func(&mut ctx);
+            ctx.consume_edges();
+            ctx.update();
+        }
+
+        Ok(())
+    }
+
+    /// Runs the game with a fixed timestep. Note: this method blocks, as it uses an infinite loop.
+    ///
+    /// `update` is called `timestep.tick_rate` times per second with the length of a tick in
+    /// seconds, no matter how fast the machine draws frames. `draw` is called once per frame with
+    /// how far the game is between the previous tick and the next one (from `0.0` to `1.0`),
+    /// which can be used to interpolate positions.
+    ///
+    /// Both callbacks get mutable access to `state`, so the game's data doesn't have to be shared
+    /// between two closures.
+    ///
+    /// ```no_run
+    /// # use cat_box::{Game, objects::timer::Timestep};
+    /// # let game = Game::new("Cool game", 1000, 1000);
+    /// let mut x = 0.0;
+    /// game.run_fixed(
+    ///     Timestep::new(60),
+    ///     &mut x,
+    ///     |x, _ctx, dt| {
+    ///         // Move 100 pixels per second, whatever the frame rate
+    ///         *x += 100.0 * dt;
+    ///     },
+    ///     |x, _ctx, _alpha| {
+    ///         // Drawing goes here
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn run_fixed<S, U, D>(
+        &self,
+        timestep: Timestep,
+        state: &mut S,
+        mut update: U,
+        mut draw: D,
+    ) -> Result<()>
+    where
+        U: FnMut(&mut S, &mut Context, f64),
+        D: FnMut(&mut S, &mut Context, f64),
+    {
+        let mut ctx = self.open_context()?;
+        let mut clock = FixedClock::new(timestep);
+        let dt = timestep.dt().as_secs_f64();
+
+        loop {
+            if self.stopped.get() || ctx.check_for_quit() {
+                break;
+            }
+            let frame_start = Instant::now();
+
+            for _ in 0..clock.advance() {
+                update(state, &mut ctx, dt);
+                ctx.consume_edges();
+            }
+
+            ctx.clear();
+            draw(state, &mut ctx, clock.alpha());
+            ctx.update();
+
+            clock.limit_frame(frame_start);
+        }
+
+        Ok(())
+    }
+
+    /// Runs the game as a stack of [`Scene`]s, starting with `scene`. Note: this method blocks, as
+    /// it uses an infinite loop.
+    ///
+    /// The loop uses a fixed timestep like [`Self::run_fixed()`], and stops once every scene has
+    /// been popped off. See the [`scene`] module for an example.
+    pub fn run_scenes<S: Scene + 'static>(&self, timestep: Timestep, scene: S) -> Result<()> {
+        let mut ctx = self.open_context()?;
+        let mut clock = FixedClock::new(timestep);
+        let dt = timestep.dt().as_secs_f64();
+
+        let mut scenes = SceneStack::new(&mut ctx, Box::new(scene));
+
+        loop {
+            if self.stopped.get() {
+                scenes.clear(&mut ctx);
+            }
+            if scenes.is_empty() {
+                break;
+            }
+            let frame_start = Instant::now();
+
+            for event in ctx.poll_events() {
+                if let Event::Quit { .. } = event {
+                    scenes.clear(&mut ctx);
+                    break;
+                }
+                scenes.handle_event(&mut ctx, &event);
+            }
+
+            for _ in 0..clock.advance() {
+                scenes.update(&mut ctx, dt);
+                ctx.consume_edges();
+            }
+
+            ctx.clear();
+            scenes.draw(&mut ctx, clock.alpha());
+            ctx.update();
+
+            clock.limit_frame(frame_start);
+        }
+
+        Ok(())
+    }
+
+    /// Runs the game for `frames` frames without opening a window, then returns the context so
+    /// the last frame can be inspected. See [`Context::headless()`] for the details.
+    ///
+    /// `script` is a list of `(frame, event)` pairs. Each event is delivered at the start of its
+    /// frame, counting from `0`.
+    ///
+    /// ```
+    /// # use cat_box::{get_keyboard_state, Event, Game, Scancode};
+    /// # use cat_box::sdl2::keyboard::Mod;
+    /// let game = Game::new("test", 100, 100);
+    /// let jump = Event::KeyDown {
+    ///     timestamp: 0,
+    ///     window_id: 0,
+    ///     keycode: None,
+    ///     scancode: Some(Scancode::Space),
+    ///     keymod: Mod::NOMOD,
+    ///     repeat: false,
+    /// };
+    ///
+    /// let mut jumped = false;
+    /// let ctx = game
+    ///     .run_headless(3, vec![(1, jump)], |ctx| {
+    ///         ctx.set_background_colour(255, 0, 0);
+    ///         jumped |= get_keyboard_state(ctx).keys.contains(&Scancode::Space);
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert!(jumped);
+    /// assert_eq!(&ctx.pixels().unwrap()[..4], &[255, 0, 0, 255]);
+    /// ```
+    pub fn run_headless<I, F>(&self, frames: u32, script: I, mut func: F) -> Result<Context>
+    where
+        I: IntoIterator<Item = (u32, Event)>,
+        F: FnMut(&mut Context),
+    {
+        let mut ctx = Context::headless(self.width.into(), self.height.into())?;
+
+        let mut script: Vec<_> = script.into_iter().collect();
+        script.sort_by_key(|(frame, _)| *frame);
+        let mut script = script.into_iter().peekable();
+
+        for frame in 0..frames {
+            while let Some((_, event)) = script.next_if(|(f, _)| *f <= frame) {
+                ctx.push_event(event);
+            }
+
+            if self.stopped.get() || ctx.check_for_quit() {
+                break;
+            }
+            ctx.clear();
+            func(&mut ctx);
+            ctx.consume_edges();
+            ctx.update();
+        }
+
+        Ok(ctx)
+    }
+
+    fn open_context(&self) -> Result<Context> {
+        let sdl_context = sdl2::init().map_err(CatboxError::sdl)?;
+        let video_subsystem = sdl_context.video().map_err(CatboxError::sdl)?;
 
         let mut window_build =
             video_subsystem.window(&self.title, self.width as u32, self.height as u32);
@@ -494,20 +1140,11 @@ impl Game {
         let canvas = window.into_canvas().build()?;
         let s = sdl2::ttf::init()?;
 
-        let event_pump = sdl_context.event_pump()?;
-
-        let mut ctx = Context::new(canvas, event_pump, s);
+        let event_pump = sdl_context.event_pump().map_err(CatboxError::sdl)?;
 
-        loop {
-            if self.stopped.get() || ctx.check_for_quit() {
-                break;
-            }
-            ctx.clear();
-            func(&mut ctx);
-            ctx.update();
-        }
+        let controllers = sdl_context.game_controller().ok();
 
-        Ok(())
+        Ok(Context::new(canvas, event_pump, s, controllers))
     }
 
     /// Runs the game from a raw pointer to a Window (blocks)
@@ -521,9 +1158,10 @@ impl Game {
         let canvas = window.into_canvas().build()?;
         let s = sdl2::ttf::init()?;
 
-        let event_pump = sdl_context.event_pump()?;
+        let event_pump = sdl_context.event_pump().map_err(CatboxError::sdl)?;
+        let controllers = sdl_context.game_controller().ok();
 
-        let mut ctx = Context::new(canvas, event_pump, s);
+        let mut ctx = Context::new(canvas, event_pump, s, controllers);
 
         loop {
             if self.stopped.get() || ctx.check_for_quit() {
@@ -531,6 +1169,7 @@ impl Game {
             }
             ctx.clear();
             func(&mut ctx);
+            ctx.consume_edges();
             ctx.update();
         }
 
@@ -552,10 +1191,15 @@ impl Game {
 #[cfg(feature = "audio")]
 #[cfg_attr(docsrs, doc(cfg(feature = "audio")))]
 /// Plays an audio file given the path of file and plays it for y seconds
+///
+/// This opens a new output and thread for every sound, and the sound can't be stopped early.
+/// Prefer [`Context::audio()`] with a preloaded [`SoundEffect`](audio::sound::SoundEffect).
 /// ```no_run
+/// # #![allow(deprecated)]
 /// # use cat_box::play;
 /// play("/path/to/song.mp71", 15);
 /// ```
+#[deprecated(note = "use `Context::audio()` and `audio::sound::SoundEffect` instead")]
 pub fn play<P: AsRef<Path> + Send + 'static>(
     path: P,
     time: u64,
@@ -205,6 +205,12 @@ impl From<(i32, i32)> for Vec2 {
     }
 }
 
+impl From<Vec2Int> for Vec2 {
+    fn from(v: Vec2Int) -> Self {
+        v.to_f32()
+    }
+}
+
 impl From<(f32, f32)> for Vec2 {
     fn from(v: (f32, f32)) -> Self {
         Self { x: v.0, y: v.1 }
@@ -0,0 +1,333 @@
+//! Sprite sheets and frame animation.
+//!
+//! ```no_run
+//! # use cat_box::{Game, objects::{animation::{AnimatedSprite, Animation, PlayMode}, sprite::Sprite, timer::Timestep}};
+//! # let game = Game::new("animation demo", 1000, 800);
+//! let sheet = Sprite::new("player.png", 500, 400).unwrap();
+//! let mut player = AnimatedSprite::from_grid(sheet, 32, 32);
+//! player.add_animation("idle", Animation::new(0..4, 0.2, PlayMode::Loop));
+//! player.add_animation("jump", Animation::new(4..8, 0.1, PlayMode::Once));
+//! player.play("idle");
+//!
+//! game.run_fixed(
+//!     Timestep::default(),
+//!     &mut player,
+//!     |player, _ctx, dt| player.update(dt),
+//!     |player, ctx, _alpha| player.draw(ctx).unwrap(),
+//! )
+//! .unwrap();
+//! ```
+
+use sdl2::rect::Rect;
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use crate::objects::sprite::Sprite;
+
+/// How an [`Animation`] continues once it reaches its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Start over from the first frame.
+    Loop,
+    /// Play backwards to the first frame, then forwards again, and so on.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+}
+
+/// A sequence of frames from a sprite sheet, each shown for its own duration.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<usize>,
+    durations: Vec<f64>,
+    mode: PlayMode,
+}
+
+impl Animation {
+    /// Creates a new animation where every frame is shown for `frame_duration` seconds.
+    ///
+    /// `frames` are indices into the sprite sheet, in the order they are played.
+    /// ```
+    /// # use cat_box::objects::animation::{Animation, PlayMode};
+    /// let walk = Animation::new(8..12, 0.1, PlayMode::Loop);
+    /// let blink = Animation::new([0, 1, 0], 0.05, PlayMode::Once);
+    /// ```
+    pub fn new<I: IntoIterator<Item = usize>>(
+        frames: I,
+        frame_duration: f64,
+        mode: PlayMode,
+    ) -> Self {
+        let frames: Vec<usize> = frames.into_iter().collect();
+        let durations = vec![frame_duration; frames.len()];
+
+        Self {
+            frames,
+            durations,
+            mode,
+        }
+    }
+
+    /// Creates a new animation from `(frame, duration in seconds)` pairs.
+    /// ```
+    /// # use cat_box::objects::animation::{Animation, PlayMode};
+    /// let attack = Animation::with_durations([(0, 0.3), (1, 0.05), (2, 0.2)], PlayMode::Once);
+    /// ```
+    pub fn with_durations<I: IntoIterator<Item = (usize, f64)>>(frames: I, mode: PlayMode) -> Self {
+        let (frames, durations) = frames.into_iter().unzip();
+
+        Self {
+            frames,
+            durations,
+            mode,
+        }
+    }
+
+    /// The number of frames in this animation.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// The total duration of one pass through the animation, in seconds.
+    #[must_use]
+    pub fn duration(&self) -> f64 {
+        self.durations.iter().sum()
+    }
+}
+
+/// A [`Sprite`] that draws one frame of a sprite sheet at a time.
+///
+/// This dereferences to the underlying [`Sprite`], so it can be moved, rotated and checked for
+/// collisions like any other sprite.
+pub struct AnimatedSprite {
+    sprite: Sprite,
+    frames: Vec<Rect>,
+    animations: HashMap<String, Animation>,
+    current: Option<String>,
+    frame: usize,
+    step: usize,
+    elapsed: f64,
+    reversed: bool,
+    finished: bool,
+}
+
+impl AnimatedSprite {
+    /// Split `sheet` into a grid of `frame_width` by `frame_height` frames.
+    ///
+    /// Frames are numbered left to right, then top to bottom. Any leftover pixels at the right and
+    /// bottom edges are ignored.
+    /// ```
+    /// # use cat_box::objects::{animation::AnimatedSprite, sprite::Sprite};
+    /// let sheet = Sprite::new("duck.png", 500, 400).unwrap();
+    /// let duck = AnimatedSprite::from_grid(sheet, 16, 16);
+    /// ```
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn from_grid(sheet: Sprite, frame_width: u32, frame_height: u32) -> Self {
+        let (width, height) = sheet.image_size();
+        let frame_width = frame_width.max(1);
+        let frame_height = frame_height.max(1);
+
+        let frames = (0..height / frame_height)
+            .flat_map(|row| {
+                (0..width / frame_width).map(move |col| {
+                    Rect::new(
+                        (col * frame_width) as i32,
+                        (row * frame_height) as i32,
+                        frame_width,
+                        frame_height,
+                    )
+                })
+            })
+            .collect();
+
+        Self::from_atlas(sheet, frames)
+    }
+
+    /// Use `frames` as the frames of `sheet`, for sheets that aren't laid out as a grid.
+    /// ```
+    /// # use cat_box::{objects::{animation::AnimatedSprite, sprite::Sprite}, sdl2::rect::Rect};
+    /// let sheet = Sprite::new("duck.png", 500, 400).unwrap();
+    /// let duck = AnimatedSprite::from_atlas(sheet, vec![Rect::new(0, 0, 20, 30), Rect::new(20, 0, 24, 30)]);
+    /// ```
+    #[must_use]
+    pub fn from_atlas(sheet: Sprite, frames: Vec<Rect>) -> Self {
+        let mut s = Self {
+            sprite: sheet,
+            frames,
+            animations: HashMap::new(),
+            current: None,
+            frame: 0,
+            step: 0,
+            elapsed: 0.0,
+            reversed: false,
+            finished: false,
+        };
+        s.set_frame(0);
+
+        s
+    }
+
+    /// Register an animation under `name`, replacing any animation that already had that name.
+    ///
+    /// Replacing the animation that is playing starts the new one from its first frame.
+    pub fn add_animation<S: Into<String>>(&mut self, name: S, animation: Animation) {
+        let name = name.into();
+        let replaces_current = self.current.as_ref() == Some(&name);
+        self.animations.insert(name, animation);
+
+        if replaces_current {
+            self.restart();
+        }
+    }
+
+    /// Start playing the animation called `name` from its first frame.
+    ///
+    /// Does nothing if that animation is already playing, so this can be called every frame.
+    /// Returns `false` if there is no animation called `name`.
+    pub fn play(&mut self, name: &str) -> bool {
+        if self.current.as_deref() == Some(name) {
+            return true;
+        }
+        if !self.animations.contains_key(name) {
+            return false;
+        }
+
+        self.current = Some(name.to_string());
+        self.restart();
+
+        true
+    }
+
+    /// Play the current animation again from its first frame.
+    pub fn restart(&mut self) {
+        self.step = 0;
+        self.elapsed = 0.0;
+        self.reversed = false;
+        self.finished = false;
+        self.show_step();
+    }
+
+    /// Stop animating, keeping the current frame on screen.
+    pub fn stop(&mut self) {
+        self.current = None;
+    }
+
+    /// The name of the animation that is playing, if any.
+    #[must_use]
+    pub fn current_animation(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    /// Whether a [`PlayMode::Once`] animation has reached its last frame.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advance the current animation by `dt` seconds.
+    pub fn update(&mut self, dt: f64) {
+        let anim = match &self.current {
+            Some(current) => &self.animations[current],
+            None => return,
+        };
+        if self.finished || anim.is_empty() {
+            return;
+        }
+
+        self.elapsed += dt;
+        while self.elapsed >= anim.durations[self.step] {
+            self.elapsed -= anim.durations[self.step].max(0.0);
+            let last = anim.len() - 1;
+
+            match anim.mode {
+                PlayMode::Loop => self.step = (self.step + 1) % anim.len(),
+                PlayMode::Once if self.step == last => {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                    break;
+                }
+                PlayMode::Once => self.step += 1,
+                PlayMode::PingPong if last == 0 => {}
+                PlayMode::PingPong => {
+                    if self.step == last {
+                        self.reversed = true;
+                    } else if self.step == 0 {
+                        self.reversed = false;
+                    }
+
+                    if self.reversed {
+                        self.step -= 1;
+                    } else {
+                        self.step += 1;
+                    }
+                }
+            }
+
+            if anim.durations[self.step] <= 0.0 {
+                break;
+            }
+        }
+
+        self.show_step();
+    }
+
+    /// The index of the sprite sheet frame that is being shown.
+    #[must_use]
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Show the sprite sheet frame at `index`, resizing the sprite to fit it.
+    ///
+    /// This doesn't stop the current animation, so it will be overwritten on the next
+    /// [`update()`](Self::update()) unless [`stop()`](Self::stop()) is called first.
+    pub fn set_frame(&mut self, index: usize) {
+        if let Some(&frame) = self.frames.get(index) {
+            let center = self.sprite.rect.center();
+            self.sprite.rect.resize(frame.width(), frame.height());
+            self.sprite.rect.center_on(center);
+            self.sprite.set_source_rect(Some(frame));
+            self.frame = index;
+        }
+    }
+
+    /// The number of frames in the sprite sheet.
+    #[must_use]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    fn show_step(&mut self) {
+        let frame = self
+            .current
+            .as_ref()
+            .and_then(|c| self.animations.get(c))
+            .and_then(|anim| anim.frames.get(self.step).copied());
+
+        if let Some(frame) = frame {
+            self.set_frame(frame);
+        }
+    }
+}
+
+impl Deref for AnimatedSprite {
+    type Target = Sprite;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sprite
+    }
+}
+
+impl DerefMut for AnimatedSprite {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sprite
+    }
+}
@@ -40,6 +40,17 @@ impl Button {
 
         self.sprite.draw(ctx)
     }
+    /// Whether the button was clicked with the left mouse button since the last update.
+    ///
+    /// Unlike [`Self::clicked()`], this fires exactly once per click.
+    #[must_use]
+    pub fn was_clicked(&self, ctx: &Context) -> bool {
+        let input = ctx.input();
+        let (x, y) = input.mouse_position().into();
+
+        input.just_pressed(MouseButton::Left) && self.sprite.rect.contains_point((x, y))
+    }
+
     pub fn clicked(&mut self, m: &MouseRepr, f: fn()) {
         let state = m.buttons.get(0).unwrap_or_else(|| &MouseButton::Unknown);
         /*if state == &MouseButton::Left {
@@ -0,0 +1,154 @@
+//! A 2d camera, for drawing sprites and text in world space.
+//!
+//! Once a camera is set with [`Context::set_camera()`](crate::Context::set_camera()), positions
+//! passed to [`Sprite::draw()`](crate::objects::sprite::Sprite::draw()) and
+//! [`draw_text()`](crate::draw_text()) are treated as world coordinates, and scrolling a level is
+//! just a matter of moving the camera.
+//!
+//! ```no_run
+//! # use cat_box::{Game, get_mouse_state, objects::{camera::Camera2D, sprite::Sprite}};
+//! # let game = Game::new("camera demo", 1000, 800);
+//! # let mut player = Sprite::new("duck.png", 500, 400).unwrap();
+//! game.run(|ctx| {
+//!     let (x, y) = player.position().into();
+//!     ctx.set_camera(Some(Camera2D::new((x, y))));
+//!     player.draw(ctx).unwrap();
+//!
+//!     let m = get_mouse_state(ctx);
+//!     let world_mouse = ctx.screen_to_world((m.x, m.y));
+//!
+//!     // Draw the HUD in screen space
+//!     ctx.set_camera(None);
+//! })
+//! .unwrap();
+//! ```
+
+use sdl2::rect::Rect;
+
+use crate::math::vec2::Vec2;
+
+/// A camera looking at the 2d world.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera2D {
+    /// The point in the world shown at the center of the viewport.
+    pub position: Vec2,
+    /// How much the world is scaled up, `1.0` being no zoom.
+    pub zoom: f32,
+    /// The angle of the camera, in degrees of clockwise rotation.
+    ///
+    /// Rotating the camera clockwise makes the world appear to rotate anticlockwise.
+    pub rotation: f64,
+    /// The part of the screen the camera draws to, or `None` for the whole screen.
+    ///
+    /// Anything drawn outside of the viewport is clipped.
+    pub viewport: Option<Rect>,
+}
+
+impl Camera2D {
+    /// Creates a new camera centered on `position`, with no zoom or rotation.
+    /// ```
+    /// # use cat_box::objects::camera::Camera2D;
+    /// let camera = Camera2D::new((500, 400));
+    /// ```
+    pub fn new<V: Into<Vec2>>(position: V) -> Self {
+        Self {
+            position: position.into(),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: None,
+        }
+    }
+
+    /// Move the camera by the given offset, in world units.
+    ///
+    /// Like [`Sprite::translate()`](crate::objects::sprite::Sprite::translate()), positive y moves
+    /// the camera up, even though [`Self::position`] has y pointing down.
+    /// ```
+    /// # use cat_box::objects::camera::Camera2D;
+    /// let mut camera = Camera2D::new((100, 100));
+    /// camera.translate((10, 20));
+    ///
+    /// assert_eq!(camera.position, (110, 80));
+    /// ```
+    pub fn translate<V: Into<Vec2>>(&mut self, offset: V) {
+        let offset = offset.into();
+        self.position += Vec2::new(offset.x, -offset.y);
+    }
+
+    /// Convert a point in the world to a point on the screen.
+    ///
+    /// `screen` is the size of the whole screen, used when there is no viewport.
+    /// ```
+    /// # use cat_box::objects::camera::Camera2D;
+    /// let mut camera = Camera2D::new((100, 100));
+    /// camera.zoom = 2.0;
+    ///
+    /// assert_eq!(camera.world_to_screen((110, 100), (200, 200)), (120, 100));
+    /// ```
+    #[must_use]
+    pub fn world_to_screen<V: Into<Vec2>>(&self, point: V, screen: (u32, u32)) -> Vec2 {
+        let offset = (point.into() - self.position) * self.zoom;
+
+        self.view_center(screen) + rotated(offset, -self.rotation)
+    }
+
+    /// Convert a point on the screen, like the mouse position, to a point in the world.
+    ///
+    /// `screen` is the size of the whole screen, used when there is no viewport.
+    /// ```
+    /// # use cat_box::objects::camera::Camera2D;
+    /// let mut camera = Camera2D::new((100, 100));
+    /// camera.zoom = 2.0;
+    ///
+    /// assert_eq!(camera.screen_to_world((120, 100), (200, 200)), (110, 100));
+    /// ```
+    #[must_use]
+    pub fn screen_to_world<V: Into<Vec2>>(&self, point: V, screen: (u32, u32)) -> Vec2 {
+        let offset = (point.into() - self.view_center(screen)) / self.zoom;
+
+        self.position + rotated(offset, self.rotation)
+    }
+
+    /// Convert a rect in the world, along with its angle, to where it ends up on the screen.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub(crate) fn project(&self, rect: Rect, angle: f64, screen: (u32, u32)) -> (Rect, f64) {
+        let center = self.world_to_screen(rect.center(), screen).rounded();
+        #[allow(clippy::cast_precision_loss)]
+        let (w, h) = (
+            (rect.width() as f32 * self.zoom).round() as u32,
+            (rect.height() as f32 * self.zoom).round() as u32,
+        );
+
+        (
+            Rect::from_center((center.x, center.y), w, h),
+            angle - self.rotation,
+        )
+    }
+
+    fn view_center(&self, screen: (u32, u32)) -> Vec2 {
+        match self.viewport {
+            Some(viewport) => viewport.center().into(),
+            #[allow(clippy::cast_precision_loss)]
+            None => Vec2::new(screen.0 as f32 / 2.0, screen.1 as f32 / 2.0),
+        }
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new((0, 0))
+    }
+}
+
+/// Rotate `v` clockwise (on screen) by `degrees`.
+#[allow(clippy::cast_possible_truncation)]
+fn rotated(v: Vec2, degrees: f64) -> Vec2 {
+    if degrees == 0.0 {
+        return v;
+    }
+
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (sin, cos) = (sin as f32, cos as f32);
+
+    Vec2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
@@ -0,0 +1,181 @@
+//! Pixel perfect collisions, from which pixels of an image are see-through.
+
+use sdl2::rect::Rect;
+
+use super::{shape::Shape, sprite::Sprite};
+use crate::{math::vec2::Vec2, Result};
+
+/// Which pixels of an image are solid, for [pixel perfect collisions](Sprite::set_collision_mask()).
+///
+/// Masks are made from the image a sprite was loaded from, with [`Sprite::alpha_mask()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionMask {
+    width: u32,
+    height: u32,
+    threshold: u8,
+    /// One bit per pixel, with each row starting on a new word.
+    bits: Vec<u64>,
+}
+
+impl CollisionMask {
+    /// Decode an image and mark the pixels more opaque than `threshold` as solid.
+    pub(crate) fn from_image_bytes(bytes: &[u8], threshold: u8) -> Result<Self> {
+        let image = image::load_from_memory(bytes)?.to_rgba8();
+        let (width, height) = image.dimensions();
+
+        let words_per_row = Self::words_per_row(width);
+        let mut bits = vec![0; words_per_row * height as usize];
+        for (x, y, pixel) in image.enumerate_pixels() {
+            if pixel[3] > threshold {
+                let (x, y) = (x as usize, y as usize);
+                bits[y * words_per_row + x / 64] |= 1 << (x % 64);
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            threshold,
+            bits,
+        })
+    }
+
+    fn words_per_row(width: u32) -> usize {
+        (width as usize).div_ceil(64)
+    }
+
+    /// The width of the image, in pixels.
+    #[must_use]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image, in pixels.
+    #[must_use]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// How opaque a pixel had to be to count as solid.
+    #[must_use]
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// Whether the pixel at (`x`, `y`) in the image is solid. Pixels outside of the image aren't.
+    #[must_use]
+    pub fn is_solid(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        self.bits[y * Self::words_per_row(self.width) + x / 64] & (1 << (x % 64)) != 0
+    }
+}
+
+/// A sprite that's ready to be asked which points in the world it covers.
+enum Solid<'a> {
+    Mask {
+        mask: &'a CollisionMask,
+        source: Rect,
+        rect: Rect,
+        center: Vec2,
+        sin: f32,
+        cos: f32,
+    },
+    Shape(Shape),
+}
+
+impl<'a> Solid<'a> {
+    fn new(sprite: &'a Sprite) -> Self {
+        match sprite.collision_mask() {
+            Some(mask) => {
+                let rect = sprite.rect;
+                #[allow(clippy::cast_precision_loss)]
+                let center = Vec2::new(
+                    rect.x() as f32 + rect.width() as f32 / 2.0,
+                    rect.y() as f32 + rect.height() as f32 / 2.0,
+                );
+                // Points are turned back the other way to find where they are on the image.
+                #[allow(clippy::cast_possible_truncation)]
+                let angle = (-sprite.angle()).to_radians() as f32;
+
+                Self::Mask {
+                    mask,
+                    source: sprite
+                        .source_rect()
+                        .unwrap_or_else(|| Rect::new(0, 0, mask.width(), mask.height())),
+                    rect,
+                    center,
+                    sin: angle.sin(),
+                    cos: angle.cos(),
+                }
+            }
+            None => Self::Shape(sprite.placed_shape()),
+        }
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Self::Mask {
+                mask,
+                source,
+                rect,
+                center,
+                sin,
+                cos,
+            } => {
+                let offset = point - *center;
+                let local = Vec2::new(
+                    offset.x * cos - offset.y * sin,
+                    offset.x * sin + offset.y * cos,
+                );
+
+                // Stretch the point from the size the sprite is drawn at to the size of the image.
+                #[allow(clippy::cast_precision_loss)]
+                let (u, v) = (
+                    (local.x / rect.width() as f32 + 0.5) * source.width() as f32,
+                    (local.y / rect.height() as f32 + 0.5) * source.height() as f32,
+                );
+                #[allow(clippy::cast_precision_loss)]
+                if u < 0.0 || v < 0.0 || u >= source.width() as f32 || v >= source.height() as f32 {
+                    return false;
+                }
+
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss,
+                    clippy::cast_possible_wrap
+                )]
+                let (x, y) = (
+                    (source.x() + u as i32) as u32,
+                    (source.y() + v as i32) as u32,
+                );
+                mask.is_solid(x, y)
+            }
+            Self::Shape(shape) => shape.contains(point),
+        }
+    }
+}
+
+/// Whether any pixel in the world is covered by both sprites.
+///
+/// Sprites without a mask are solid wherever their collision shape is.
+pub(crate) fn pixels_overlap(sprite1: &Sprite, sprite2: &Sprite) -> bool {
+    let Some(overlap) = sprite1
+        .collision_bounds()
+        .intersection(sprite2.collision_bounds())
+    else {
+        return false;
+    };
+
+    let (solid1, solid2) = (Solid::new(sprite1), Solid::new(sprite2));
+    (overlap.top()..overlap.bottom()).any(|y| {
+        (overlap.left()..overlap.right()).any(|x| {
+            #[allow(clippy::cast_precision_loss)]
+            let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            solid1.contains(center) && solid2.contains(center)
+        })
+    })
+}
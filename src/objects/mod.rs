@@ -1,4 +1,10 @@
+pub mod animation;
 pub mod button;
+pub mod camera;
+pub mod mask;
 pub mod physics;
+pub mod platformer;
+pub mod shape;
+mod spatial_hash;
 pub mod sprite;
 pub mod timer;
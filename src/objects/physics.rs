@@ -4,41 +4,69 @@
 
 #![allow(clippy::cast_possible_wrap)]
 
-use crate::objects::sprite::{Sprite, SpriteCollection};
-use std::cmp::max;
+use crate::math::vec2::Vec2Int;
+use crate::objects::{
+    mask::pixels_overlap,
+    shape::Contact,
+    sprite::{Sprite, SpriteCollection},
+};
+use sdl2::rect::Rect;
+use std::{cmp::max, ptr};
 
 // https://github.com/pythonarcade/arcade/blob/d2ce45a9b965020cde57a2a88536311e04504e6e/arcade/sprite_list/spatial_hash.py#L356
 
 fn collided(sprite1: &Sprite, sprite2: &Sprite) -> bool {
-    let coll_rad1 = max(sprite1.rect.width(), sprite1.rect.height()) as i32;
-    let coll_rad2 = max(sprite2.rect.width(), sprite2.rect.height()) as i32;
-
-    let collision_radius = coll_rad1 + coll_rad2;
-    let collision_diameter = collision_radius * collision_radius;
+    if sprite1.collision_mask().is_some() || sprite2.collision_mask().is_some() {
+        return pixels_overlap(sprite1, sprite2);
+    }
 
-    let diff_x = sprite1.position().x - sprite2.position().x;
-    let diff_x2 = diff_x * diff_x;
+    contact(sprite1, sprite2).is_some()
+}
 
-    if diff_x2 > collision_diameter {
-        return false;
+fn contact(sprite1: &Sprite, sprite2: &Sprite) -> Option<Contact> {
+    // Most sprites aren't anywhere near each other, so check their bounds before their shapes.
+    if !sprite1
+        .collision_bounds()
+        .has_intersection(sprite2.collision_bounds())
+    {
+        return None;
     }
 
-    let diff_y = sprite1.position().y - sprite2.position().y;
-    let diff_y2 = diff_y * diff_y;
-
-    if diff_y2 > collision_diameter {
-        return false;
+    // The shapes still give the direction to push the sprites apart in.
+    let contact = sprite1.placed_shape().contact(&sprite2.placed_shape());
+    if sprite1.collision_mask().is_some() || sprite2.collision_mask().is_some() {
+        return contact.filter(|_| pixels_overlap(sprite1, sprite2));
     }
 
-    sprite1.rect.has_intersection(sprite2.rect)
+    contact
 }
 
 /// Check if two sprites are touching or overlapping.
+///
+/// This uses each sprite's [collision shape](Sprite::set_collision_shape()), rotated by its angle.
 #[must_use]
 pub fn check_for_collision(sprite1: &Sprite, sprite2: &Sprite) -> bool {
     collided(sprite1, sprite2)
 }
 
+/// Check if two sprites are overlapping, and if they are, find the quickest way to push them
+/// apart.
+///
+/// ```
+/// # use cat_box::{*, objects::sprite::Sprite};
+/// let mut player = Sprite::new("duck.png", 500, 400).unwrap();
+/// let wall = Sprite::new("duck.png", 520, 400).unwrap();
+/// if let Some(contact) = check_for_contact(&player, &wall) {
+///     // Move the player back out of the wall.
+///     let push = contact.normal * -contact.depth;
+///     player.rect.offset(push.x.round() as i32, push.y.round() as i32);
+/// }
+/// ```
+#[must_use]
+pub fn check_for_contact(sprite1: &Sprite, sprite2: &Sprite) -> Option<Contact> {
+    contact(sprite1, sprite2)
+}
+
 #[must_use]
 pub fn check_for_collision_with_point(
     sprite1: &Sprite,
@@ -67,13 +95,155 @@ pub fn check_for_collision_with_point(
 
 /// Check if the sprite is colliding with any sprite in the collection, and return a list of
 /// references to the sprites which are colliding
+///
+/// If the collection has a [spatial hash](SpriteCollection::with_spatial_hash()), only the
+/// sprites near `sprite` are checked.
 #[must_use]
 pub fn check_for_collision_with_collection<'a>(
     sprite: &Sprite,
     list: &'a SpriteCollection,
 ) -> Vec<&'a Sprite> {
-    list.inner()
-        .iter()
-        .filter(|s| check_for_collision(sprite, s))
+    match list.nearby(sprite.collision_bounds()) {
+        Some(nearby) => nearby
+            .into_iter()
+            .map(|i| &list[i])
+            .filter(|s| check_for_collision(sprite, s))
+            .collect(),
+        None => list
+            .inner()
+            .iter()
+            .filter(|s| check_for_collision(sprite, s))
+            .collect(),
+    }
+}
+
+/// Check every sprite in `first` against every sprite in `second`, and return each pair that is
+/// colliding, ordered by their place in `first` and then in `second`.
+///
+/// If either collection has a [spatial hash](SpriteCollection::with_spatial_hash()), each sprite
+/// is only checked against the sprites near it.
+/// ```
+/// # use cat_box::{*, objects::sprite::SpriteCollection};
+/// let bullets = SpriteCollection::new();
+/// let enemies = SpriteCollection::new().with_spatial_hash(128);
+/// for (_bullet, enemy) in check_for_collision_between_collections(&bullets, &enemies) {
+///     println!("hit an enemy at {:?}", enemy.position());
+/// }
+/// ```
+#[must_use]
+pub fn check_for_collision_between_collections<'a, 'b>(
+    first: &'a SpriteCollection,
+    second: &'b SpriteCollection,
+) -> Vec<(&'a Sprite, &'b Sprite)> {
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    if second.spatial_hash_cell_size().is_some() {
+        for (i, sprite) in first.inner().iter().enumerate() {
+            let nearby = second.nearby(sprite.collision_bounds()).unwrap_or_default();
+            pairs.extend(nearby.into_iter().map(|j| (i, j)));
+        }
+    } else if first.spatial_hash_cell_size().is_some() {
+        for (j, sprite) in second.inner().iter().enumerate() {
+            let nearby = first.nearby(sprite.collision_bounds()).unwrap_or_default();
+            pairs.extend(nearby.into_iter().map(|i| (i, j)));
+        }
+        pairs.sort_unstable();
+    } else {
+        pairs.extend((0..first.len()).flat_map(|i| (0..second.len()).map(move |j| (i, j))));
+    }
+
+    pairs
+        .into_iter()
+        .map(|(i, j)| (&first[i], &second[j]))
+        .filter(|(a, b)| check_for_collision(a, b))
+        .collect()
+}
+
+/// Move `sprite` by `velocity`, one axis at a time, stopping at any wall in `walls`, and return
+/// how far it actually moved.
+///
+/// Like arcade's `PhysicsEngineSimple`, the sprite moves along x and then along y, so running
+/// into a wall at an angle slides along it instead of stopping dead. It moves a pixel at a time,
+/// so it can't skip through thin walls however fast it's going. A wall the sprite is already
+/// overlapping only stops it from going any deeper, so it can always get back out of it.
+///
+/// `velocity` is in the same coordinates as [`Sprite::rect`], where y points down.
+/// ```
+/// # use cat_box::{*, objects::sprite::{Sprite, SpriteCollection}};
+/// let mut player = Sprite::new("duck.png", 500, 400).unwrap();
+/// let mut walls = SpriteCollection::new();
+/// walls.push(Sprite::new("duck.png", 700, 400).unwrap());
+///
+/// let moved = move_and_slide(&mut player, (300, 5), &walls);
+/// if moved.x != 300 {
+///     println!("bumped into a wall");
+/// }
+/// ```
+pub fn move_and_slide<V: Into<Vec2Int>>(
+    sprite: &mut Sprite,
+    velocity: V,
+    walls: &SpriteCollection,
+) -> Vec2Int {
+    let velocity = velocity.into();
+    let slide = |sprite: &mut Sprite, step, steps| {
+        let stuck = stuck_in(sprite, walls);
+        step_until_blocked(sprite, step, steps, |sprite, _| {
+            blocked_by(sprite, walls, &stuck)
+        })
+    };
+
+    let x = slide(sprite, (velocity.x.signum(), 0), velocity.x.unsigned_abs());
+    let y = slide(sprite, (0, velocity.y.signum()), velocity.y.unsigned_abs());
+
+    Vec2Int::new(
+        velocity.x.signum() * x as i32,
+        velocity.y.signum() * y as i32,
+    )
+}
+
+/// The sprites in `list` that `sprite` is already overlapping, and how deep.
+pub(crate) fn stuck_in<'a>(sprite: &Sprite, list: &'a SpriteCollection) -> Vec<(&'a Sprite, f32)> {
+    check_for_collision_with_collection(sprite, list)
+        .into_iter()
+        .map(|solid| (solid, contact(sprite, solid).map_or(0.0, |c| c.depth)))
         .collect()
 }
+
+/// Whether `sprite` is touching anything in `list`, apart from the sprites it was `stuck_in`
+/// before it moved, as long as it isn't any deeper in them.
+pub(crate) fn blocked_by(
+    sprite: &Sprite,
+    list: &SpriteCollection,
+    stuck_in: &[(&Sprite, f32)],
+) -> bool {
+    check_for_collision_with_collection(sprite, list)
+        .into_iter()
+        .any(
+            |solid| match stuck_in.iter().find(|(s, _)| ptr::eq(*s, solid)) {
+                Some(&(_, depth)) => contact(sprite, solid).is_some_and(|c| c.depth > depth),
+                None => true,
+            },
+        )
+}
+
+/// Move `sprite` up to `steps` times by `step`, stopping before the first step that `blocked`
+/// refuses, and return how many steps it took.
+///
+/// Moving a pixel at a time means nothing can be skipped over, however fast the sprite is going.
+/// `blocked` is given the sprite after the step and its collision bounds from before it.
+pub(crate) fn step_until_blocked<F: FnMut(&Sprite, Rect) -> bool>(
+    sprite: &mut Sprite,
+    step: (i32, i32),
+    steps: u32,
+    mut blocked: F,
+) -> u32 {
+    for taken in 0..steps {
+        let before = sprite.collision_bounds();
+        sprite.rect.offset(step.0, step.1);
+        if blocked(sprite, before) {
+            sprite.rect.offset(-step.0, -step.1);
+            return taken;
+        }
+    }
+
+    steps
+}
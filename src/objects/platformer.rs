@@ -0,0 +1,377 @@
+//! A physics engine for platformers, modelled on arcade's `PhysicsEnginePlatformer`.
+//!
+//! The engine owns the level: the walls, the one-way platforms, the ladders and the moving
+//! platforms. Each frame, set the player's horizontal [velocity](PhysicsEnginePlatformer::velocity)
+//! from the input and call [`update()`](PhysicsEnginePlatformer::update()), which pulls the player
+//! down, moves them and stops them at anything solid.
+//!
+//! ```no_run
+//! # use cat_box::{Game, Scancode, objects::{platformer::PhysicsEnginePlatformer, sprite::{Sprite, SpriteCollection}}};
+//! # let game = Game::new("platformer demo", 1000, 800);
+//! let mut player = Sprite::new("duck.png", 100, 100).unwrap();
+//! let mut walls = SpriteCollection::new();
+//! walls.push(Sprite::new("duck.png", 100, 400).unwrap());
+//!
+//! let mut engine = PhysicsEnginePlatformer::new(walls, 0.5).with_max_jumps(2);
+//! game.run(|ctx| {
+//!     let input = ctx.input();
+//!     engine.velocity.x = match (input.is_pressed(Scancode::Left), input.is_pressed(Scancode::Right)) {
+//!         (true, false) => -5.0,
+//!         (false, true) => 5.0,
+//!         _ => 0.0,
+//!     };
+//!     if input.just_pressed(Scancode::Space) {
+//!         engine.jump(12.0);
+//!     }
+//!
+//!     engine.update(&mut player);
+//!     engine.draw(ctx).unwrap();
+//!     player.draw(ctx).unwrap();
+//! })
+//! .unwrap();
+//! ```
+
+use sdl2::rect::Rect;
+
+use super::{
+    physics::{
+        blocked_by, check_for_collision, check_for_collision_with_collection, step_until_blocked,
+        stuck_in,
+    },
+    sprite::{Sprite, SpriteCollection},
+};
+use crate::{
+    math::vec2::{Vec2, Vec2Int},
+    Context, Result,
+};
+
+/// How a moving platform moves.
+#[derive(Clone, Copy, Debug)]
+struct Motion {
+    velocity: Vec2Int,
+    path: Rect,
+}
+
+/// Moves a player sprite with gravity, and stops it at the walls of the level.
+///
+/// Velocities are in pixels per update, in the same coordinates as
+/// [`Sprite::rect`](super::sprite::Sprite::rect): y points down, so gravity is positive and
+/// jumping is negative.
+pub struct PhysicsEnginePlatformer {
+    /// How fast the player is moving.
+    ///
+    /// Set `x` every frame to walk. `y` is handled by gravity and [jumping](Self::jump()), except
+    /// on ladders, where there's no gravity and it's up to you.
+    pub velocity: Vec2,
+    /// How much the player's vertical velocity grows every update.
+    pub gravity: f32,
+    max_fall_speed: Option<f32>,
+    max_jumps: u32,
+    jumps: u32,
+    walls: SpriteCollection,
+    platforms: SpriteCollection,
+    ladders: SpriteCollection,
+    moving: SpriteCollection,
+    motions: Vec<Motion>,
+    /// The part of the velocity that didn't add up to a whole pixel yet.
+    remainder: Vec2,
+    on_ground: bool,
+    on_ladder: bool,
+}
+
+impl PhysicsEnginePlatformer {
+    /// Create an engine where the player can't move through any of the sprites in `walls`.
+    /// ```
+    /// # use cat_box::objects::{platformer::PhysicsEnginePlatformer, sprite::SpriteCollection};
+    /// let engine = PhysicsEnginePlatformer::new(SpriteCollection::new(), 0.5);
+    /// ```
+    #[must_use]
+    pub fn new(walls: SpriteCollection, gravity: f32) -> Self {
+        Self {
+            velocity: Vec2::default(),
+            gravity,
+            max_fall_speed: None,
+            max_jumps: 1,
+            jumps: 0,
+            walls,
+            platforms: SpriteCollection::new(),
+            ladders: SpriteCollection::new(),
+            moving: SpriteCollection::new(),
+            motions: Vec::new(),
+            remainder: Vec2::default(),
+            on_ground: false,
+            on_ladder: false,
+        }
+    }
+
+    /// Add one-way platforms, which the player can jump up through and land on top of.
+    #[must_use]
+    pub fn with_platforms(mut self, platforms: SpriteCollection) -> Self {
+        self.platforms = platforms;
+        self
+    }
+
+    /// Add ladders. The player doesn't fall while touching one, and can climb down through
+    /// one-way platforms.
+    #[must_use]
+    pub fn with_ladders(mut self, ladders: SpriteCollection) -> Self {
+        self.ladders = ladders;
+        self
+    }
+
+    /// Add a solid platform that moves by `velocity` every update, turning around whenever it
+    /// would leave `path`.
+    ///
+    /// `path` has to be big enough to fit the platform. A player standing on the platform is
+    /// carried along with it, and one in its way is pushed.
+    /// ```
+    /// # use cat_box::{sdl2::rect::Rect, objects::{platformer::PhysicsEnginePlatformer, sprite::{Sprite, SpriteCollection}}};
+    /// let lift = Sprite::new("duck.png", 300, 400).unwrap();
+    /// let path = Rect::new(lift.rect.x(), 100, lift.rect.width(), 400);
+    /// let engine = PhysicsEnginePlatformer::new(SpriteCollection::new(), 0.5)
+    ///     .with_moving_platform(lift, (0, 2), path);
+    /// ```
+    #[must_use]
+    pub fn with_moving_platform<V: Into<Vec2Int>>(
+        mut self,
+        platform: Sprite,
+        velocity: V,
+        path: Rect,
+    ) -> Self {
+        self.moving.push(platform);
+        self.motions.push(Motion {
+            velocity: velocity.into(),
+            path,
+        });
+        self
+    }
+
+    /// Let the player jump `max_jumps` times before landing again, counting the jump off the
+    /// ground. The default is `1`.
+    #[must_use]
+    pub fn with_max_jumps(mut self, max_jumps: u32) -> Self {
+        self.max_jumps = max_jumps.max(1);
+        self
+    }
+
+    /// Stop gravity from making the player fall faster than `speed`.
+    #[must_use]
+    pub fn with_max_fall_speed(mut self, speed: f32) -> Self {
+        self.max_fall_speed = Some(speed);
+        self
+    }
+
+    /// The walls of the level.
+    #[must_use]
+    pub fn walls(&self) -> &SpriteCollection {
+        &self.walls
+    }
+
+    /// The walls of the level, to add or remove some.
+    pub fn walls_mut(&mut self) -> &mut SpriteCollection {
+        &mut self.walls
+    }
+
+    /// The one-way platforms.
+    #[must_use]
+    pub fn platforms(&self) -> &SpriteCollection {
+        &self.platforms
+    }
+
+    /// The one-way platforms, to add or remove some.
+    pub fn platforms_mut(&mut self) -> &mut SpriteCollection {
+        &mut self.platforms
+    }
+
+    /// The ladders.
+    #[must_use]
+    pub fn ladders(&self) -> &SpriteCollection {
+        &self.ladders
+    }
+
+    /// The ladders, to add or remove some.
+    pub fn ladders_mut(&mut self) -> &mut SpriteCollection {
+        &mut self.ladders
+    }
+
+    /// The moving platforms, in the order they were added.
+    #[must_use]
+    pub fn moving_platforms(&self) -> &SpriteCollection {
+        &self.moving
+    }
+
+    /// Whether the player was standing on something at the end of the last update.
+    #[must_use]
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    /// Whether the player was touching a ladder during the last update.
+    #[must_use]
+    pub fn is_on_ladder(&self) -> bool {
+        self.on_ladder
+    }
+
+    /// Whether [`jump()`](Self::jump()) would do anything.
+    ///
+    /// The player can jump off the ground, or in the air if they have jumps left. There's no
+    /// jumping off ladders.
+    #[must_use]
+    pub fn can_jump(&self) -> bool {
+        if self.on_ladder {
+            return false;
+        }
+
+        self.on_ground || (self.max_jumps > 1 && self.jumps < self.max_jumps)
+    }
+
+    /// Jump, by setting the player's vertical velocity to `speed` upwards.
+    ///
+    /// Returns whether the player [could jump](Self::can_jump()).
+    pub fn jump(&mut self, speed: f32) -> bool {
+        if !self.can_jump() {
+            return false;
+        }
+
+        self.velocity.y = -speed;
+        self.jumps += 1;
+        self.on_ground = false;
+        true
+    }
+
+    /// Move the moving platforms and then the player, one axis at a time.
+    ///
+    /// The player's velocity along an axis is reset to `0` when they run into something on it.
+    pub fn update(&mut self, player: &mut Sprite) {
+        self.move_platforms(player);
+
+        self.on_ladder = hits(player, &self.ladders);
+        if !self.on_ladder {
+            self.velocity.y += self.gravity;
+            if let Some(max) = self.max_fall_speed {
+                self.velocity.y = self.velocity.y.min(max);
+            }
+        }
+
+        self.remainder += self.velocity;
+        #[allow(clippy::cast_possible_truncation)]
+        let (dx, dy) = (self.remainder.x as i32, self.remainder.y as i32);
+        self.remainder -= Vec2::from((dx, dy));
+
+        if self.move_player(player, (dx.signum(), 0), dx.unsigned_abs()) {
+            self.velocity.x = 0.0;
+            self.remainder.x = 0.0;
+        }
+        if self.move_player(player, (0, dy.signum()), dy.unsigned_abs()) {
+            self.velocity.y = 0.0;
+            self.remainder.y = 0.0;
+        }
+
+        self.on_ground = self.is_standing(player);
+        if self.on_ground {
+            self.jumps = 0;
+        }
+    }
+
+    /// Draw the ladders, walls, one-way platforms and moving platforms, in that order.
+    pub fn draw(&mut self, ctx: &mut Context) -> Result<()> {
+        self.ladders.draw(ctx)?;
+        self.walls.draw(ctx)?;
+        self.platforms.draw(ctx)?;
+        self.moving.draw(ctx)
+    }
+
+    fn move_platforms(&mut self, player: &mut Sprite) {
+        for (i, motion) in self.motions.iter_mut().enumerate() {
+            let riding = rides(player, &self.moving[i]);
+
+            let rect = self.moving[i].rect;
+            let (dx, dy) = (motion.velocity.x, motion.velocity.y);
+            if rect.left() + dx < motion.path.left() || rect.right() + dx > motion.path.right() {
+                motion.velocity.x = -dx;
+            }
+            if rect.top() + dy < motion.path.top() || rect.bottom() + dy > motion.path.bottom() {
+                motion.velocity.y = -dy;
+            }
+            let (dx, dy) = (motion.velocity.x, motion.velocity.y);
+            self.moving[i].rect.offset(dx, dy);
+
+            if riding || check_for_collision(player, &self.moving[i]) {
+                let walls = &self.walls;
+                let stuck = stuck_in(player, walls);
+                step_until_blocked(player, (dx.signum(), 0), dx.unsigned_abs(), |p, _| {
+                    blocked_by(p, walls, &stuck)
+                });
+                let stuck = stuck_in(player, walls);
+                step_until_blocked(player, (0, dy.signum()), dy.unsigned_abs(), |p, _| {
+                    blocked_by(p, walls, &stuck)
+                });
+            }
+        }
+    }
+
+    /// Move the player and return whether they ran into something.
+    fn move_player(&self, player: &mut Sprite, step: (i32, i32), steps: u32) -> bool {
+        let falling = step.1 > 0;
+        let stuck = self.stuck_in(player);
+        step_until_blocked(player, step, steps, |p, before| {
+            self.blocked(p, before, falling, &stuck)
+        }) < steps
+    }
+
+    /// The walls and moving platforms the player is already overlapping, like when a level
+    /// starts that way or a moving platform squashes them into a wall.
+    ///
+    /// These only stop the player from going deeper, so they can always get back out.
+    fn stuck_in(&self, player: &Sprite) -> Vec<(&Sprite, f32)> {
+        let mut stuck = stuck_in(player, &self.walls);
+        stuck.extend(stuck_in(player, &self.moving));
+        stuck
+    }
+
+    /// Whether the player can't be where they are, after moving from `before`.
+    fn blocked(
+        &self,
+        player: &Sprite,
+        before: Rect,
+        falling: bool,
+        stuck: &[(&Sprite, f32)],
+    ) -> bool {
+        blocked_by(player, &self.walls, stuck)
+            || blocked_by(player, &self.moving, stuck)
+            || (falling && !self.on_ladder && lands_on(player, before, &self.platforms))
+    }
+
+    fn is_standing(&self, player: &mut Sprite) -> bool {
+        let before = player.collision_bounds();
+        let stuck = self.stuck_in(player);
+        player.rect.offset(0, 1);
+        let standing = self.blocked(player, before, true, &stuck);
+        player.rect.offset(0, -1);
+        standing
+    }
+}
+
+fn hits(sprite: &Sprite, list: &SpriteCollection) -> bool {
+    !check_for_collision_with_collection(sprite, list).is_empty()
+}
+
+/// Whether `sprite` has just fallen onto one of the one-way `platforms`, from being above it at
+/// `before`.
+fn lands_on(sprite: &Sprite, before: Rect, platforms: &SpriteCollection) -> bool {
+    check_for_collision_with_collection(sprite, platforms)
+        .into_iter()
+        .any(|platform| before.bottom() <= platform.collision_bounds().top())
+}
+
+/// Whether `player` is standing on top of `platform`.
+fn rides(player: &mut Sprite, platform: &Sprite) -> bool {
+    if check_for_collision(player, platform) {
+        return false;
+    }
+
+    player.rect.offset(0, 1);
+    let riding = check_for_collision(player, platform);
+    player.rect.offset(0, -1);
+    riding
+}
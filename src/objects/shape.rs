@@ -0,0 +1,326 @@
+//! Collision shapes for sprites, which turn with them.
+//!
+//! Collisions between shapes are found with the separating axis theorem: two convex shapes are
+//! only touching if there's no line they can be projected onto without overlapping. Checking the
+//! edges of each shape is enough, and the axis they overlap on the least is the quickest way to
+//! push them apart.
+
+use sdl2::rect::Rect;
+
+use crate::math::vec2::Vec2;
+
+/// The shape of a sprite for collisions, in pixels from the center of its rect.
+///
+/// The shape is rotated by the sprite's [angle](super::sprite::Sprite::set_angle()), around the
+/// center of its rect, just like it's drawn.
+/// ```
+/// # use cat_box::objects::{shape::CollisionShape, sprite::Sprite};
+/// let mut ball = Sprite::new("duck.png", 500, 400).unwrap();
+/// ball.set_collision_shape(CollisionShape::Circle { radius: 20.0 });
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum CollisionShape {
+    /// The sprite's rect. This is the default.
+    #[default]
+    Rect,
+    /// A circle around the center of the sprite.
+    Circle { radius: f32 },
+    /// A convex polygon. Make one with [`CollisionShape::polygon()`] to be sure it's convex.
+    Polygon(Vec<Vec2>),
+}
+
+impl CollisionShape {
+    /// The smallest convex polygon around all of `points`.
+    ///
+    /// Concave shapes are filled in, and shapes with fewer than 3 corners never collide.
+    /// ```
+    /// # use cat_box::{math::vec2::Vec2, objects::shape::CollisionShape};
+    /// let triangle = CollisionShape::polygon(vec![(0.0, -20.0), (20.0, 20.0), (-20.0, 20.0)]);
+    ///
+    /// // Points inside the shape or along its edges aren't corners.
+    /// let square = CollisionShape::polygon(vec![
+    ///     (5.0, 5.0),
+    ///     (0.0, 10.0),
+    ///     (10.0, 10.0),
+    ///     (5.0, 0.0),
+    ///     (10.0, 0.0),
+    ///     (0.0, 0.0),
+    ///     (10.0, 10.0),
+    /// ]);
+    /// let corners = [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)];
+    /// assert_eq!(square, CollisionShape::Polygon(corners.iter().map(|&c| Vec2::from(c)).collect()));
+    /// ```
+    pub fn polygon<V: Into<Vec2>, I: IntoIterator<Item = V>>(points: I) -> Self {
+        Self::Polygon(convex_hull(points.into_iter().map(Into::into).collect()))
+    }
+
+    /// Find how the shape overlaps `other`, with each placed on a rect without being rotated.
+    ///
+    /// [`check_for_contact()`](super::physics::check_for_contact()) does this for two sprites,
+    /// rotated by their angles.
+    /// ```
+    /// # use cat_box::{math::vec2::Vec2, objects::shape::{CollisionShape, Contact}, sdl2::rect::Rect};
+    /// let (player, wall) = (Rect::new(0, 0, 20, 20), Rect::new(15, 5, 20, 20));
+    /// let contact = CollisionShape::Rect.contact(player, &CollisionShape::Rect, wall);
+    /// assert_eq!(contact, Some(Contact { normal: Vec2::new(1.0, 0.0), depth: 5.0 }));
+    ///
+    /// let ball = CollisionShape::Circle { radius: 10.0 };
+    /// let contact = ball.contact(player, &ball, Rect::new(15, 0, 20, 20));
+    /// assert_eq!(contact, Some(Contact { normal: Vec2::new(1.0, 0.0), depth: 5.0 }));
+    /// assert_eq!(ball.contact(player, &CollisionShape::Rect, Rect::new(25, 0, 20, 20)), None);
+    ///
+    /// let line = CollisionShape::polygon(vec![(-10.0, 0.0), (10.0, 0.0)]);
+    /// assert_eq!(line.contact(player, &CollisionShape::Rect, player), None);
+    /// ```
+    #[must_use]
+    pub fn contact(&self, rect: Rect, other: &Self, other_rect: Rect) -> Option<Contact> {
+        self.transform(rect, 0.0)
+            .contact(&other.transform(other_rect, 0.0))
+    }
+
+    /// Place the shape on a sprite's `rect`, rotated `angle` degrees clockwise.
+    pub(crate) fn transform(&self, rect: Rect, angle: f64) -> Shape {
+        #[allow(clippy::cast_precision_loss)]
+        let center = Vec2::new(
+            rect.x() as f32 + rect.width() as f32 / 2.0,
+            rect.y() as f32 + rect.height() as f32 / 2.0,
+        );
+        #[allow(clippy::cast_possible_truncation)]
+        let (sin, cos) = (
+            angle.to_radians().sin() as f32,
+            angle.to_radians().cos() as f32,
+        );
+        let place = |p: Vec2| center + Vec2::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos);
+
+        match self {
+            Self::Rect => {
+                #[allow(clippy::cast_precision_loss)]
+                let (w, h) = (rect.width() as f32 / 2.0, rect.height() as f32 / 2.0);
+                let corners = [(-w, -h), (w, -h), (w, h), (-w, h)];
+                Shape::Polygon(corners.iter().map(|&c| place(c.into())).collect())
+            }
+            Self::Circle { radius } => Shape::Circle {
+                center,
+                radius: *radius,
+            },
+            Self::Polygon(points) => Shape::Polygon(points.iter().map(|&p| place(p)).collect()),
+        }
+    }
+}
+
+/// How two shapes are overlapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// The direction from the first shape into the second, with a length of `1`.
+    ///
+    /// This is in the same coordinates as [`Sprite::rect`](super::sprite::Sprite::rect), where y
+    /// points down.
+    pub normal: Vec2,
+    /// How far the first shape has to move against the normal, or the second along it, for them
+    /// to only be touching.
+    pub depth: f32,
+}
+
+/// A collision shape that has been placed in the world.
+#[derive(Clone, Debug)]
+pub(crate) enum Shape {
+    Circle { center: Vec2, radius: f32 },
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    /// The smallest rect that the shape fits in.
+    pub(crate) fn bounds(&self) -> Rect {
+        let (min, max) = match self {
+            Self::Circle { center, radius } => (
+                *center - Vec2::new(*radius, *radius),
+                *center + Vec2::new(*radius, *radius),
+            ),
+            Self::Polygon(points) => points.iter().fold(
+                (
+                    Vec2::new(f32::INFINITY, f32::INFINITY),
+                    Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+                ),
+                |(min, max), p| {
+                    (
+                        Vec2::new(min.x.min(p.x), min.y.min(p.y)),
+                        Vec2::new(max.x.max(p.x), max.y.max(p.y)),
+                    )
+                },
+            ),
+        };
+        if min.x > max.x || min.y > max.y {
+            return Rect::new(0, 0, 0, 0);
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Rect::new(
+            min.x.floor() as i32,
+            min.y.floor() as i32,
+            (max.x.ceil() - min.x.floor()).max(1.0) as u32,
+            (max.y.ceil() - min.y.floor()).max(1.0) as u32,
+        )
+    }
+
+    /// Whether `point` is inside the shape.
+    pub(crate) fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Self::Circle { center, radius } => point.sq_dist(*center) < radius * radius,
+            Self::Polygon(points) if points.len() < 3 => false,
+            // The point has to be on the same side of every edge.
+            Self::Polygon(points) => {
+                let mut sides = (0..points.len())
+                    .map(|i| cross(points[i], points[(i + 1) % points.len()], point));
+                sides.clone().all(|side| side > 0.0) || sides.all(|side| side < 0.0)
+            }
+        }
+    }
+
+    /// The lowest and highest points of the shape along `axis`.
+    fn project(&self, axis: Vec2) -> (f32, f32) {
+        match self {
+            Self::Circle { center, radius } => {
+                let c = dot(*center, axis);
+                (c - radius, c + radius)
+            }
+            Self::Polygon(points) => points
+                .iter()
+                .map(|&p| dot(p, axis))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                    (min.min(d), max.max(d))
+                }),
+        }
+    }
+
+    /// The axes that might separate this shape from `other`.
+    fn axes(&self, other: &Self) -> Vec<Vec2> {
+        match self {
+            Self::Polygon(points) => (0..points.len())
+                .map(|i| {
+                    let edge = points[(i + 1) % points.len()] - points[i];
+                    Vec2::new(-edge.y, edge.x)
+                })
+                .filter(|normal| normal.sq_magnitude() > 0.0)
+                .map(Vec2::normalized)
+                .collect(),
+            // A circle can only be separated from a polygon along the line to its closest corner.
+            Self::Circle { center, .. } => match other {
+                Self::Polygon(points) => points
+                    .iter()
+                    .map(|&p| p - *center)
+                    .min_by(|a, b| a.sq_magnitude().total_cmp(&b.sq_magnitude()))
+                    .filter(|closest| closest.sq_magnitude() > 0.0)
+                    .map(Vec2::normalized)
+                    .into_iter()
+                    .collect(),
+                Self::Circle { .. } => Vec::new(),
+            },
+        }
+    }
+
+    /// Find how `self` and `other` overlap, or `None` if they don't.
+    pub(crate) fn contact(&self, other: &Self) -> Option<Contact> {
+        if let Self::Polygon(points) = self {
+            if points.len() < 3 {
+                return None;
+            }
+        }
+        if let Self::Polygon(points) = other {
+            if points.len() < 3 {
+                return None;
+            }
+        }
+
+        if let (
+            Self::Circle { center, radius },
+            Self::Circle {
+                center: other_center,
+                radius: other_radius,
+            },
+        ) = (self, other)
+        {
+            let between = *other_center - *center;
+            let depth = radius + other_radius - between.magnitude();
+            if depth <= 0.0 {
+                return None;
+            }
+            let normal = if between.sq_magnitude() > 0.0 {
+                between.normalized()
+            } else {
+                Vec2::new(1.0, 0.0)
+            };
+            return Some(Contact { normal, depth });
+        }
+
+        let mut best: Option<Contact> = None;
+        for axis in self.axes(other).into_iter().chain(other.axes(self)) {
+            let (min, max) = self.project(axis);
+            let (other_min, other_max) = other.project(axis);
+
+            // How far `self` has to move back along the axis, or forward, to stop overlapping.
+            let back = max - other_min;
+            let forward = other_max - min;
+            if back <= 0.0 || forward <= 0.0 {
+                return None;
+            }
+
+            let contact = if back < forward {
+                Contact {
+                    normal: axis,
+                    depth: back,
+                }
+            } else {
+                Contact {
+                    normal: -axis,
+                    depth: forward,
+                }
+            };
+            if best.is_none_or(|best| contact.depth < best.depth) {
+                best = Some(contact);
+            }
+        }
+
+        best
+    }
+}
+
+fn dot(a: Vec2, b: Vec2) -> f32 {
+    a.x * b.x + a.y * b.y
+}
+
+fn cross(o: Vec2, a: Vec2, b: Vec2) -> f32 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+/// The corners of the smallest convex polygon around `points`, in order.
+pub(crate) fn convex_hull(mut points: Vec<Vec2>) -> Vec<Vec2> {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+
+    // Andrew's monotone chain: build the bottom and then the top half, dropping any points that
+    // would make the hull turn the wrong way.
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() + 1);
+    for &p in &points {
+        push_turning(&mut hull, 2, p);
+    }
+    let top = hull.len() + 1;
+    for &p in points.iter().rev().skip(1) {
+        push_turning(&mut hull, top, p);
+    }
+    // The first point was added again at the end.
+    hull.pop();
+
+    hull
+}
+
+/// Add `p` to the end of the hull, after removing corners that wouldn't be convex, as long as the
+/// hull stays at least `min_len - 1` long.
+fn push_turning(hull: &mut Vec<Vec2>, min_len: usize, p: Vec2) {
+    while hull.len() >= min_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+        hull.pop();
+    }
+    hull.push(p);
+}
@@ -0,0 +1,87 @@
+//! A grid of buckets for finding the sprites near a point without checking all of them.
+
+use sdl2::rect::Rect;
+use std::{collections::HashMap, convert::TryFrom};
+
+use super::sprite::Sprite;
+
+/// Sprites sorted into square cells by where their rects are.
+///
+/// Sprites are moved through `&mut` access to their collection, which only marks the hash as
+/// dirty. It's rebuilt the next time it's queried, so moving every sprite each frame only costs
+/// one rebuild.
+#[derive(Clone, Debug)]
+pub(crate) struct SpatialHash {
+    cell_size: i32,
+    /// The indices of the sprites touching each cell.
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    dirty: bool,
+}
+
+impl SpatialHash {
+    pub(crate) fn new(cell_size: u32) -> Self {
+        Self {
+            cell_size: i32::try_from(cell_size.max(1)).unwrap_or(i32::MAX),
+            cells: HashMap::new(),
+            dirty: true,
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    pub(crate) fn cell_size(&self) -> u32 {
+        self.cell_size as u32
+    }
+
+    pub(crate) fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// The indices of every sprite in a cell that `rect` touches, in order.
+    ///
+    /// These are only the sprites that *might* be touching `rect`, they still need to be checked.
+    pub(crate) fn query(&mut self, sprites: &[Sprite], rect: Rect) -> Vec<usize> {
+        if self.dirty {
+            self.rebuild(sprites);
+        }
+
+        let mut found: Vec<usize> = self
+            .cells(rect)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+
+        found
+    }
+
+    fn rebuild(&mut self, sprites: &[Sprite]) {
+        // Keep the buckets around, most sprites stay in the same cells from frame to frame.
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        for (i, sprite) in sprites.iter().enumerate() {
+            for cell in self.cells(sprite.collision_bounds()) {
+                self.cells.entry(cell).or_default().push(i);
+            }
+        }
+        self.cells.retain(|_, bucket| !bucket.is_empty());
+
+        self.dirty = false;
+    }
+
+    /// Every cell that `rect` covers.
+    fn cells(&self, rect: Rect) -> impl Iterator<Item = (i32, i32)> {
+        let cell = |n: i32| n.div_euclid(self.cell_size);
+        #[allow(clippy::cast_possible_wrap)]
+        let (right, bottom) = (
+            rect.x().saturating_add(rect.width() as i32 - 1),
+            rect.y().saturating_add(rect.height() as i32 - 1),
+        );
+        let (min_x, max_x) = (cell(rect.x()), cell(right));
+        let (min_y, max_y) = (cell(rect.y()), cell(bottom));
+
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+}
@@ -1,25 +1,41 @@
 use image::{codecs::png::PngEncoder, imageops::FilterType, ImageReader};
 use sdl2::{
     image::ImageRWops, /*     pixels::{Color, PixelFormatEnum}, */
-    rect::Rect, rwops::RWops, surface::Surface,
+    pixels::PixelFormatEnum, rect::Rect, rwops::RWops, surface::Surface,
 };
 use std::{
+    cell::RefCell,
     io::{Cursor, Read},
     ops::{Deref, DerefMut},
     path::Path,
+    rc::Rc,
     slice::IterMut,
 };
 
-use crate::math::vec2::Vec2Int;
+use super::{
+    mask::CollisionMask,
+    shape::{CollisionShape, Shape},
+    spatial_hash::SpatialHash,
+};
+use crate::math::vec2::{Vec2, Vec2Int};
+use crate::texture::{texture_key, SharedTexture};
 
-use crate::{Context, Result};
+use crate::{CatboxError, Context, Result};
 
 /// Representation of a sprite.
+///
+/// The sprite's texture is uploaded to the GPU the first time it is drawn, and is shared with
+/// every other sprite that was loaded from the same bytes.
 pub struct Sprite {
     pub rect: Rect,
     surf: Surface<'static>,
     angle: f64,
     bytes: Vec<u8>,
+    texture_key: u64,
+    texture: Option<Rc<SharedTexture>>,
+    source: Option<Rect>,
+    shape: CollisionShape,
+    mask: Option<CollisionMask>,
 }
 
 impl Sprite {
@@ -31,14 +47,18 @@ impl Sprite {
     /// let s = Sprite::new("duck.png", 500, 400).unwrap();
     /// ```
     pub fn new<P: AsRef<Path>>(path: P, x: i32, y: i32) -> Result<Self> {
+        let path = path.as_ref();
         let mut buf: Vec<u8> = Vec::new();
-        let mut f = std::fs::File::open(path)?;
-        f.read_to_end(&mut buf)?;
+        let mut f = std::fs::File::open(path).map_err(|e| CatboxError::asset(path, e))?;
+        f.read_to_end(&mut buf)
+            .map_err(|e| CatboxError::asset(path, e))?;
         /* let ops = RWops::from_file(path, "r")?; */
         // let sec_buf = buf.clone();
-        let ops = RWops::from_bytes(buf.as_ref())?;
+        let ops = RWops::from_bytes(buf.as_ref()).map_err(CatboxError::sdl)?;
         let buf: Vec<u8> = <Vec<u8> as AsRef<[u8]>>::as_ref(&buf).to_owned();
-        let surf = ops.load()?;
+        let surf = ops
+            .load()
+            .map_err(|e| CatboxError::asset(path, CatboxError::sdl(e)))?;
 
         let srect = surf.rect();
         let dest_rect: Rect = Rect::from_center((x, y), srect.width(), srect.height());
@@ -47,7 +67,12 @@ impl Sprite {
             rect: dest_rect,
             surf,
             angle: 0.0,
+            texture_key: texture_key(&buf),
             bytes: buf,
+            texture: None,
+            source: None,
+            shape: CollisionShape::Rect,
+            mask: None,
         })
     }
 
@@ -60,8 +85,8 @@ impl Sprite {
     /// let s = Sprite::from_bytes(bytes, 500, 400).unwrap();
     /// ```
     pub fn from_bytes<B: AsRef<[u8]>>(bytes: B, x: i32, y: i32) -> Result<Self> {
-        let ops = RWops::from_bytes(bytes.as_ref())?;
-        let surf = ops.load()?;
+        let ops = RWops::from_bytes(bytes.as_ref()).map_err(CatboxError::sdl)?;
+        let surf = ops.load().map_err(CatboxError::sdl)?;
         let bytes: Vec<u8> = bytes.as_ref().to_vec();
         let srect = surf.rect();
         let dest_rect: Rect = Rect::from_center((x, y), srect.width(), srect.height());
@@ -70,12 +95,19 @@ impl Sprite {
             rect: dest_rect,
             surf,
             angle: 0.0,
+            texture_key: texture_key(&bytes),
             bytes,
+            texture: None,
+            source: None,
+            shape: CollisionShape::Rect,
+            mask: None,
         })
     }
 
     /// Draws the sprite to the window. This should only be called inside your main event loop.
     ///
+    /// If a [camera](crate::Context::set_camera()) is set, the sprite is drawn in world space.
+    ///
     /// ```no_run
     /// # use cat_box::*;
     /// # let mut s = Sprite::new("duck.png", 500, 400).unwrap();
@@ -85,10 +117,20 @@ impl Sprite {
     /// # });
     /// ```
     pub fn draw(&mut self, ctx: &mut Context) -> Result<()> {
-        let (creator, canvas, _) = ctx.inner();
-        let text = creator.create_texture_from_surface(&self.surf)?;
+        self.draw_in(ctx, self.rect, self.angle)
+    }
+
+    /// Draw the sprite stretched to fill `rect` in the world instead of its own rect, like when
+    /// it's an icon in [rich text](crate::text::RichText).
+    pub(crate) fn draw_in(&mut self, ctx: &mut Context, rect: Rect, angle: f64) -> Result<()> {
+        let texture = ctx.texture(self.texture_key, &self.surf, self.texture.as_ref())?;
+        let (dest, angle) = ctx.project(rect, angle);
+        let (_, canvas, _) = ctx.inner();
 
-        canvas.copy_ex(&text, None, self.rect, self.angle, None, false, false)?;
+        canvas
+            .copy_ex(&texture, self.source, dest, angle, None, false, false)
+            .map_err(CatboxError::sdl)?;
+        self.texture = Some(texture);
 
         Ok(())
     }
@@ -109,6 +151,9 @@ impl Sprite {
         self.rect.set_y(new_y);
     }
 
+    /// Create a resized copy of this sprite.
+    ///
+    /// The copy gets its own texture the next time it is drawn.
     pub fn resize(&self, nwidth: u32, nheight: u32) -> Result<Self> {
         let mut thing = ImageReader::new(Cursor::new(self.bytes.clone()))
             .with_guessed_format()?
@@ -120,7 +165,13 @@ impl Sprite {
         let encoder = PngEncoder::new(&mut new_buf);
         thing.write_with_encoder(encoder).unwrap();
 
-        let sp = Self::from_bytes(new_buf, self.rect.x(), self.rect.y())?;
+        let mut sp = Self::from_bytes(new_buf, self.rect.x(), self.rect.y())?;
+        if let Some(mask) = &self.mask {
+            sp.mask = Some(CollisionMask::from_image_bytes(
+                &sp.bytes,
+                mask.threshold(),
+            )?);
+        }
 
         Ok(sp)
     }
@@ -168,6 +219,164 @@ impl Sprite {
         self.angle = angle;
     }
 
+    /// Only draw the given part of the image, or the whole image if `None`.
+    ///
+    /// This is what [`AnimatedSprite`](crate::objects::animation::AnimatedSprite) uses to pick a
+    /// frame out of a sprite sheet. The part is stretched to fit [`rect`](Self::rect).
+    ///
+    /// ```
+    /// # use cat_box::{objects::sprite::Sprite, sdl2::rect::Rect};
+    /// # let mut s = Sprite::new("duck.png", 500, 400).unwrap();
+    /// s.set_source_rect(Some(Rect::new(0, 0, 16, 16)));
+    /// ```
+    pub fn set_source_rect(&mut self, source: Option<Rect>) {
+        self.source = source;
+    }
+
+    /// Get the part of the image that is drawn, or `None` if the whole image is drawn.
+    #[must_use]
+    pub fn source_rect(&self) -> Option<Rect> {
+        self.source
+    }
+
+    /// Get the size of the whole image, in the form of (width, height).
+    #[must_use]
+    pub fn image_size(&self) -> (u32, u32) {
+        self.surf.size()
+    }
+
+    /// The size of the part of the image that is drawn.
+    pub(crate) fn source_size(&self) -> (u32, u32) {
+        self.source
+            .map_or_else(|| self.surf.size(), |source| source.size())
+    }
+
+    /// Set the shape used for collisions, which turns with the sprite.
+    ///
+    /// By default, this is [`CollisionShape::Rect`], the sprite's rect.
+    pub fn set_collision_shape(&mut self, shape: CollisionShape) {
+        self.shape = shape;
+    }
+
+    /// Get the shape used for collisions.
+    #[must_use]
+    pub fn collision_shape(&self) -> &CollisionShape {
+        &self.shape
+    }
+
+    /// Compute the convex hull of the pixels that are more opaque than `threshold`, to use as a
+    /// [collision shape](Self::set_collision_shape()).
+    ///
+    /// Only the [part of the image that is drawn](Self::set_source_rect()) is used, and it's
+    /// scaled to the sprite's current rect, so this should be done again if either changes. An
+    /// image without any opaque pixels gets [`CollisionShape::Rect`].
+    /// ```
+    /// # use cat_box::objects::sprite::Sprite;
+    /// let mut duck = Sprite::new("duck.png", 500, 400).unwrap();
+    /// duck.set_collision_shape(duck.alpha_hull(128).unwrap());
+    /// ```
+    pub fn alpha_hull(&self, threshold: u8) -> Result<CollisionShape> {
+        let surf = self
+            .surf
+            .convert_format(PixelFormatEnum::ARGB8888)
+            .map_err(CatboxError::sdl)?;
+        let pitch = surf.pitch() as usize;
+        let source = self.source.unwrap_or_else(|| surf.rect());
+        let (width, height) = (source.width() as usize, source.height() as usize);
+        #[allow(clippy::cast_sign_loss)]
+        let (left, top) = (source.x().max(0) as usize, source.y().max(0) as usize);
+
+        // The corners of the leftmost and rightmost opaque pixels in each row are all the hull
+        // could need.
+        let mut points = Vec::new();
+        surf.with_lock(|pixels| {
+            for y in 0..height {
+                let row = (top + y) * pitch;
+                // ARGB8888 is stored as BGRA on little endian machines.
+                let alpha = |x: usize| {
+                    let i =
+                        row + (left + x) * 4 + if cfg!(target_endian = "little") { 3 } else { 0 };
+                    pixels.get(i).copied().unwrap_or(0)
+                };
+                let mut opaque = (0..width).filter(|&x| alpha(x) > threshold);
+                if let Some(first) = opaque.next() {
+                    let last = opaque.next_back().unwrap_or(first);
+                    for &x in &[first, last + 1] {
+                        points.push((x, y));
+                        points.push((x, y + 1));
+                    }
+                }
+            }
+        });
+        if points.is_empty() {
+            return Ok(CollisionShape::Rect);
+        }
+
+        // Move the points to be around the center, and stretch them to the size the sprite is
+        // drawn at.
+        #[allow(clippy::cast_precision_loss)]
+        let (scale_x, scale_y) = (
+            self.rect.width() as f32 / width as f32,
+            self.rect.height() as f32 / height as f32,
+        );
+        #[allow(clippy::cast_precision_loss)]
+        let points = points.into_iter().map(|(x, y)| {
+            Vec2::new(
+                (x as f32 - width as f32 / 2.0) * scale_x,
+                (y as f32 - height as f32 / 2.0) * scale_y,
+            )
+        });
+
+        Ok(CollisionShape::polygon(points))
+    }
+
+    /// Make a mask of the pixels in the sprite's image that are more opaque than `threshold`, for
+    /// [pixel perfect collisions](Self::set_collision_mask()).
+    ///
+    /// The mask covers the whole image, so it works with any [source rect](Self::set_source_rect())
+    /// and however big the sprite is drawn.
+    pub fn alpha_mask(&self, threshold: u8) -> Result<CollisionMask> {
+        CollisionMask::from_image_bytes(&self.bytes, threshold)
+    }
+
+    /// Check collisions with this sprite pixel by pixel, using `mask`, or turn that off with
+    /// `None`.
+    ///
+    /// Collision checks first make sure the sprites' [bounds](Self::collision_bounds()) overlap,
+    /// and then look for a pixel that is solid in both. Sprites without a mask are solid wherever
+    /// their [collision shape](Self::set_collision_shape()) is. This is much slower than shapes
+    /// alone, so it's best kept for the few sprites that need it.
+    ///
+    /// [`resize()`](Self::resize()) makes a new mask for the resized image.
+    /// ```
+    /// # use cat_box::objects::sprite::Sprite;
+    /// let mut duck = Sprite::new("duck.png", 500, 400).unwrap();
+    /// duck.set_collision_mask(Some(duck.alpha_mask(0).unwrap()));
+    /// ```
+    pub fn set_collision_mask(&mut self, mask: Option<CollisionMask>) {
+        self.mask = mask;
+    }
+
+    /// Get the mask used for pixel perfect collisions, if there is one.
+    #[must_use]
+    pub fn collision_mask(&self) -> Option<&CollisionMask> {
+        self.mask.as_ref()
+    }
+
+    /// The collision shape, placed where the sprite is.
+    pub(crate) fn placed_shape(&self) -> Shape {
+        self.shape.transform(self.rect, self.angle)
+    }
+
+    /// The smallest rect that the collision shape fits in, after it's been rotated.
+    #[must_use]
+    pub fn collision_bounds(&self) -> Rect {
+        if self.shape == CollisionShape::Rect && self.angle % 360.0 == 0.0 {
+            return self.rect;
+        }
+        self.placed_shape().bounds()
+    }
+
     /// Get the angle of the sprite, in degrees of clockwise rotation.
     ///
     /// ```
@@ -197,9 +406,13 @@ impl Sprite {
 ///
 /// Technically, this is a thin wrapper around a simple [`Vec`] of sprites,
 /// although with some convenience methods.
+///
+/// Big collections can keep a [spatial hash](Self::with_spatial_hash()) to speed up collision
+/// checks against them.
 #[derive(Default)]
 pub struct SpriteCollection {
     v: Vec<Sprite>,
+    hash: Option<RefCell<SpatialHash>>,
 }
 
 impl SpriteCollection {
@@ -212,7 +425,10 @@ impl SpriteCollection {
     /// ```
     #[must_use]
     pub fn new() -> Self {
-        Self { v: Vec::new() }
+        Self {
+            v: Vec::new(),
+            hash: None,
+        }
     }
 
     /// Creates a new [`SpriteCollection`] with the specified capacity.
@@ -226,6 +442,55 @@ impl SpriteCollection {
     pub fn with_capacity(cap: usize) -> Self {
         Self {
             v: Vec::with_capacity(cap),
+            hash: None,
+        }
+    }
+
+    /// Keep a spatial hash of the sprites, with square cells `cell_size` pixels wide.
+    ///
+    /// Collision checks against the collection then only look at sprites in the same cells,
+    /// instead of every sprite. This is worth it for collections of more than a few dozen sprites.
+    /// Cells about as big as the biggest sprites work best.
+    ///
+    /// The hash is rebuilt the first time it's used after the sprites change, so moving all of
+    /// them and then checking for collisions only costs a single rebuild.
+    /// ```
+    /// # use cat_box::{*, objects::sprite::{Sprite, SpriteCollection}};
+    /// let walls = SpriteCollection::new().with_spatial_hash(64);
+    /// # let player = Sprite::new("duck.png", 500, 400).unwrap();
+    /// let hits = check_for_collision_with_collection(&player, &walls);
+    /// ```
+    #[must_use]
+    pub fn with_spatial_hash(mut self, cell_size: u32) -> Self {
+        self.set_spatial_hash(Some(cell_size));
+        self
+    }
+
+    /// Start using a spatial hash with cells `cell_size` pixels wide, or stop using one with
+    /// `None`.
+    ///
+    /// See [`with_spatial_hash()`](Self::with_spatial_hash()) for more info.
+    pub fn set_spatial_hash(&mut self, cell_size: Option<u32>) {
+        self.hash = cell_size.map(|size| RefCell::new(SpatialHash::new(size)));
+    }
+
+    /// The size of the spatial hash's cells, or `None` if the collection doesn't have one.
+    #[must_use]
+    pub fn spatial_hash_cell_size(&self) -> Option<u32> {
+        self.hash.as_ref().map(|hash| hash.borrow().cell_size())
+    }
+
+    /// The indices of the sprites that might be touching `rect`, or `None` without a spatial hash.
+    pub(crate) fn nearby(&self, rect: Rect) -> Option<Vec<usize>> {
+        self.hash
+            .as_ref()
+            .map(|hash| hash.borrow_mut().query(&self.v, rect))
+    }
+
+    /// Remember to rebuild the spatial hash, because the sprites might have changed.
+    fn changed(&mut self) {
+        if let Some(hash) = &mut self.hash {
+            hash.get_mut().mark_dirty();
         }
     }
 
@@ -255,6 +520,7 @@ impl SpriteCollection {
     /// sprites.push(s);
     /// ```
     pub fn push(&mut self, s: Sprite) {
+        self.changed();
         self.v.push(s);
     }
 
@@ -267,6 +533,7 @@ impl SpriteCollection {
     /// sprites.insert(s, 0);
     /// ```
     pub fn insert(&mut self, s: Sprite, index: usize) {
+        self.changed();
         self.v.insert(index, s);
     }
 
@@ -277,6 +544,7 @@ impl SpriteCollection {
     /// let s = sprites.pop();
     /// ```
     pub fn pop(&mut self) -> Option<Sprite> {
+        self.changed();
         self.v.pop()
     }
 
@@ -291,12 +559,14 @@ impl SpriteCollection {
     /// sprites.remove(0);
     /// ```
     pub fn remove(&mut self, index: usize) -> Sprite {
+        self.changed();
         self.v.remove(index)
     }
 
     /// Return an iterator over the sprites in this collection.
     /// Use this to modify the sprites themselves, for example to set their position or angle.
     pub fn iter(&mut self) -> IterMut<'_, Sprite> {
+        self.changed();
         self.v.iter_mut()
     }
 
@@ -309,6 +579,7 @@ impl SpriteCollection {
     /// sprites.clear();
     /// ```
     pub fn clear(&mut self) {
+        self.changed();
         self.v.clear();
     }
 
@@ -324,6 +595,7 @@ impl SpriteCollection {
     /// sprites.concat(sprites2);
     /// ```
     pub fn concat(&mut self, mut other: SpriteCollection) {
+        self.changed();
         self.v.append(&mut *other);
     }
 
@@ -368,6 +640,7 @@ impl Deref for SpriteCollection {
 
 impl DerefMut for SpriteCollection {
     fn deref_mut(&mut self) -> &mut Self::Target {
+        self.changed();
         &mut self.v
     }
 }
@@ -3,6 +3,105 @@ use std::{
     time::{Duration, Instant},
 };
 
+/// Settings for a fixed-timestep game loop. See [`Game::run_fixed()`](crate::Game::run_fixed()).
+///
+/// ```
+/// # use cat_box::objects::timer::Timestep;
+/// let mut timestep = Timestep::new(120);
+/// timestep.max_fps = Some(60);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timestep {
+    /// How many times per second the update callback is run.
+    pub tick_rate: u32,
+    /// The most time the loop will try to catch up on in a single frame.
+    ///
+    /// If a frame takes longer than this (for example while the window is being dragged), the
+    /// extra time is dropped instead of running a burst of updates. Anything less than
+    /// [`dt()`](Self::dt()) is treated as `dt()`, so the game still updates.
+    pub max_catch_up: Duration,
+    /// The maximum number of frames drawn per second, or `None` to draw as fast as possible.
+    pub max_fps: Option<u32>,
+}
+
+impl Timestep {
+    /// Creates a new `Timestep` that updates `tick_rate` times per second.
+    #[must_use]
+    pub fn new(tick_rate: u32) -> Self {
+        Self {
+            tick_rate,
+            ..Self::default()
+        }
+    }
+
+    /// The duration of a single update.
+    #[must_use]
+    pub fn dt(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / f64::from(self.tick_rate.max(1)))
+    }
+}
+
+impl Default for Timestep {
+    /// 60 updates per second, catching up on at most a quarter of a second, with no frame limit.
+    fn default() -> Self {
+        Self {
+            tick_rate: 60,
+            max_catch_up: Duration::from_millis(250),
+            max_fps: None,
+        }
+    }
+}
+
+/// Accumulates frame time and hands it out in fixed-size steps.
+pub(crate) struct FixedClock {
+    timestep: Timestep,
+    last: Instant,
+    accumulator: Duration,
+}
+
+impl FixedClock {
+    pub(crate) fn new(timestep: Timestep) -> Self {
+        Self {
+            timestep,
+            last: Instant::now(),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    /// Advance the clock to now, returning how many updates are due.
+    pub(crate) fn advance(&mut self) -> u32 {
+        let now = Instant::now();
+        let dt = self.timestep.dt();
+        // Catching up on less than one update would never run any.
+        self.accumulator =
+            (self.accumulator + (now - self.last)).min(self.timestep.max_catch_up.max(dt));
+        self.last = now;
+
+        let mut ticks = 0;
+        while self.accumulator >= dt {
+            self.accumulator -= dt;
+            ticks += 1;
+        }
+
+        ticks
+    }
+
+    /// How far we are between the last update and the next one, from `0.0` to `1.0`.
+    pub(crate) fn alpha(&self) -> f64 {
+        self.accumulator.as_secs_f64() / self.timestep.dt().as_secs_f64()
+    }
+
+    /// Sleep for whatever is left of the frame that started at `frame_start`.
+    pub(crate) fn limit_frame(&self, frame_start: Instant) {
+        if let Some(fps) = self.timestep.max_fps {
+            let frame = Duration::from_secs_f64(1.0 / f64::from(fps.max(1)));
+            if let Some(rest) = frame.checked_sub(frame_start.elapsed()) {
+                std::thread::sleep(rest);
+            }
+        }
+    }
+}
+
 pub struct Timer {
     time: Cell<Instant>,
 }
@@ -0,0 +1,268 @@
+//! Scenes, for splitting a game into menus, levels, pause screens and so on.
+//!
+//! Each [`Scene`] handles its own events, updates and drawing. Scenes live on a stack: the top one
+//! is active, and it decides when to push a new scene on top of itself, pop itself off, or be
+//! replaced, by returning a [`Transition`].
+//!
+//! ```no_run
+//! use cat_box::{scene::{Scene, Transition}, objects::timer::Timestep, Context, Event, Game, Scancode};
+//!
+//! struct Menu;
+//! struct Level {
+//!     time: f64,
+//! }
+//!
+//! impl Scene for Menu {
+//!     fn handle_event(&mut self, _ctx: &mut Context, event: &Event) -> Transition {
+//!         match event {
+//!             Event::KeyDown { scancode: Some(Scancode::Return), .. } => {
+//!                 Transition::Push(Box::new(Level { time: 0.0 })).fade(0.5)
+//!             }
+//!             _ => Transition::None,
+//!         }
+//!     }
+//!
+//!     fn update(&mut self, _ctx: &mut Context, _dt: f64) -> Transition {
+//!         Transition::None
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context, _alpha: f64) {
+//!         ctx.set_background_colour(0, 0, 0);
+//!     }
+//! }
+//!
+//! impl Scene for Level {
+//!     fn update(&mut self, _ctx: &mut Context, dt: f64) -> Transition {
+//!         self.time += dt;
+//!         if self.time > 60.0 {
+//!             // Back to the menu
+//!             Transition::Pop
+//!         } else {
+//!             Transition::None
+//!         }
+//!     }
+//!
+//!     fn draw(&mut self, ctx: &mut Context, _alpha: f64) {
+//!         ctx.set_background_colour(0, 128, 0);
+//!     }
+//! }
+//!
+//! let game = Game::new("scene demo", 1000, 800);
+//! game.run_scenes(Timestep::default(), Menu).unwrap();
+//! ```
+
+use sdl2::{pixels::Color, render::BlendMode};
+
+use crate::{Context, Event};
+
+/// One screen or state of the game.
+///
+/// Only [`update()`](Self::update()) and [`draw()`](Self::draw()) have to be implemented.
+pub trait Scene {
+    /// Called when the scene is pushed onto the stack.
+    fn on_enter(&mut self, _ctx: &mut Context) {}
+
+    /// Called for every event while this is the top scene.
+    fn handle_event(&mut self, _ctx: &mut Context, _event: &Event) -> Transition {
+        Transition::None
+    }
+
+    /// Called once per tick while this is the top scene, with the length of the tick in seconds.
+    fn update(&mut self, ctx: &mut Context, dt: f64) -> Transition;
+
+    /// Called once per frame. See [`Game::run_fixed()`](crate::Game::run_fixed()) for `alpha`.
+    fn draw(&mut self, ctx: &mut Context, alpha: f64);
+
+    /// Called when the scene is popped off or replaced, or the game quits.
+    fn on_exit(&mut self, _ctx: &mut Context) {}
+
+    /// Whether the scene below this one should be drawn first, for overlays like pause menus.
+    fn is_transparent(&self) -> bool {
+        false
+    }
+}
+
+/// What the scene stack should do next.
+pub enum Transition {
+    /// Stay on the current scene.
+    None,
+    /// Put a new scene on top of the current one. The current scene is kept, but paused.
+    Push(Box<dyn Scene>),
+    /// Remove the current scene, resuming the one below it.
+    Pop,
+    /// Remove the current scene and put a new one in its place.
+    Replace(Box<dyn Scene>),
+    /// Remove every scene, ending the game.
+    Quit,
+    /// Fade to black over the first half of the duration (in seconds), do the transition, and fade
+    /// back in over the second half. Create this with [`Transition::fade()`].
+    Fade(Box<Transition>, f64),
+}
+
+impl Transition {
+    /// Fade to black and back over `duration` seconds while doing this transition.
+    #[must_use]
+    pub fn fade(self, duration: f64) -> Self {
+        Self::Fade(Box::new(self), duration)
+    }
+}
+
+struct Fade {
+    pending: Option<Transition>,
+    duration: f64,
+    elapsed: f64,
+}
+
+impl Fade {
+    /// How opaque the black overlay is right now.
+    fn opacity(&self) -> u8 {
+        let half = self.duration / 2.0;
+        let t = if self.elapsed < half {
+            self.elapsed / half
+        } else {
+            (self.duration - self.elapsed) / half
+        };
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let opacity = (t.clamp(0.0, 1.0) * 255.0) as u8;
+        opacity
+    }
+}
+
+/// A stack of [`Scene`]s. Only the top scene receives events and updates.
+///
+/// [`Game::run_scenes()`](crate::Game::run_scenes()) manages one of these for you, but it can also
+/// be driven by hand, for example to test scenes with a
+/// [headless context](crate::Context::headless()).
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    fade: Option<Fade>,
+}
+
+impl SceneStack {
+    /// Creates a new stack, entering `scene`.
+    pub fn new(ctx: &mut Context, mut scene: Box<dyn Scene>) -> Self {
+        scene.on_enter(ctx);
+
+        Self {
+            scenes: vec![scene],
+            fade: None,
+        }
+    }
+
+    /// Pass an event to the top scene.
+    ///
+    /// Events are dropped while fading between scenes.
+    pub fn handle_event(&mut self, ctx: &mut Context, event: &Event) {
+        if self.fade.is_some() {
+            return;
+        }
+
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.handle_event(ctx, event);
+            self.apply(ctx, transition);
+        }
+    }
+
+    /// Update the top scene, or advance the fade between scenes.
+    pub fn update(&mut self, ctx: &mut Context, dt: f64) {
+        if let Some(fade) = &mut self.fade {
+            fade.elapsed += dt;
+
+            let pending = if fade.elapsed >= fade.duration / 2.0 {
+                fade.pending.take()
+            } else {
+                None
+            };
+            if fade.elapsed >= fade.duration {
+                self.fade = None;
+            }
+            if let Some(transition) = pending {
+                self.apply(ctx, transition);
+            }
+
+            return;
+        }
+
+        if let Some(scene) = self.scenes.last_mut() {
+            let transition = scene.update(ctx, dt);
+            self.apply(ctx, transition);
+        }
+    }
+
+    /// Draw the top scene, along with any transparent scenes' backgrounds and the fade overlay.
+    pub fn draw(&mut self, ctx: &mut Context, alpha: f64) {
+        let first = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_transparent())
+            .unwrap_or(0);
+
+        for scene in &mut self.scenes[first..] {
+            scene.draw(ctx, alpha);
+        }
+
+        if let Some(fade) = &self.fade {
+            let (_, canvas, _) = ctx.inner();
+            let colour = canvas.draw_color();
+            let blend = canvas.blend_mode();
+
+            canvas.set_blend_mode(BlendMode::Blend);
+            canvas.set_draw_color(Color::RGBA(0, 0, 0, fade.opacity()));
+            // Nothing sensible to do if this fails; the overlay just doesn't show up.
+            let _ = canvas.fill_rect(None);
+
+            canvas.set_blend_mode(blend);
+            canvas.set_draw_color(colour);
+        }
+    }
+
+    /// Exit every scene, top to bottom.
+    pub fn clear(&mut self, ctx: &mut Context) {
+        while let Some(mut scene) = self.scenes.pop() {
+            scene.on_exit(ctx);
+        }
+    }
+
+    /// The number of scenes on the stack.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// Whether every scene has been popped off, meaning the game is over.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    fn apply(&mut self, ctx: &mut Context, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut scene) => {
+                scene.on_enter(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.on_exit(ctx);
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Some(mut old) = self.scenes.pop() {
+                    old.on_exit(ctx);
+                }
+                scene.on_enter(ctx);
+                self.scenes.push(scene);
+            }
+            Transition::Quit => self.clear(ctx),
+            Transition::Fade(transition, duration) => {
+                self.fade = Some(Fade {
+                    pending: Some(*transition),
+                    duration,
+                    elapsed: 0.0,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Golden-image snapshot testing.
+//!
+//! Render a frame in a [headless](crate::Context::headless()) context, then compare it against a
+//! reference PNG that was saved earlier with [`Context::capture()`](crate::Context::capture()).
+//!
+//! ```no_run
+//! # use cat_box::{Context, snapshot::compare_snapshot};
+//! let mut ctx = Context::headless(100, 100).unwrap();
+//! // ... draw things ...
+//!
+//! // The first time, save the reference:
+//! // ctx.capture().unwrap().save("tests/snapshots/duck.png").unwrap();
+//!
+//! let result = compare_snapshot(&ctx, "tests/snapshots/duck.png", 2).unwrap();
+//! assert!(result.is_match(), "{} pixels differ", result.mismatched_pixels);
+//! ```
+
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+use crate::{CatboxError, Context, Result};
+
+/// The result of comparing two images.
+pub struct Comparison {
+    /// How many pixels differ by more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// An image highlighting the differing pixels in red, if there were any.
+    pub diff: Option<RgbaImage>,
+    /// Where the diff image was written to, if it was.
+    pub diff_path: Option<PathBuf>,
+}
+
+impl Comparison {
+    /// Whether the images matched within the tolerance.
+    #[must_use]
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compare two images pixel by pixel.
+///
+/// A pixel matches if none of its channels differ by more than `tolerance`. If the sizes differ,
+/// every pixel outside of the overlapping area counts as a mismatch.
+///
+/// ```
+/// # use cat_box::snapshot::compare_images;
+/// # use image::{Rgba, RgbaImage};
+/// let a = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+/// let b = RgbaImage::from_pixel(4, 4, Rgba([102, 100, 100, 255]));
+///
+/// assert!(compare_images(&a, &b, 2).is_match());
+/// assert_eq!(compare_images(&a, &b, 1).mismatched_pixels, 16);
+/// ```
+#[must_use]
+pub fn compare_images(actual: &RgbaImage, expected: &RgbaImage, tolerance: u8) -> Comparison {
+    let width = actual.width().max(expected.width());
+    let height = actual.height().max(expected.height());
+
+    let mut mismatched_pixels = 0;
+    let diff = RgbaImage::from_fn(width, height, |x, y| {
+        let a = actual.get_pixel_checked(x, y);
+        let e = expected.get_pixel_checked(x, y);
+
+        match (a, e) {
+            (Some(a), Some(e)) if pixels_match(*a, *e, tolerance) => faded(*e),
+            _ => {
+                mismatched_pixels += 1;
+                Rgba([255, 0, 0, 255])
+            }
+        }
+    });
+
+    Comparison {
+        mismatched_pixels,
+        diff: (mismatched_pixels > 0).then_some(diff),
+        diff_path: None,
+    }
+}
+
+/// Capture the current frame and compare it against the PNG at `reference`.
+///
+/// On a mismatch, the diff image is written next to the reference, with a `.diff.png` extension.
+pub fn compare_snapshot<P: AsRef<Path>>(
+    ctx: &Context,
+    reference: P,
+    tolerance: u8,
+) -> Result<Comparison> {
+    let reference = reference.as_ref();
+    let expected = image::open(reference)
+        .map_err(|e| CatboxError::asset(reference, e))?
+        .into_rgba8();
+    let actual = ctx.capture()?;
+
+    let mut comparison = compare_images(&actual, &expected, tolerance);
+    if let Some(diff) = &comparison.diff {
+        let path = reference.with_extension("diff.png");
+        diff.save(&path).map_err(|e| CatboxError::asset(&path, e))?;
+        comparison.diff_path = Some(path);
+    }
+
+    Ok(comparison)
+}
+
+fn pixels_match(a: Rgba<u8>, b: Rgba<u8>, tolerance: u8) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+}
+
+/// A greyed out version of a matching pixel, so the mismatches stand out.
+fn faded(p: Rgba<u8>) -> Rgba<u8> {
+    let [r, g, b, _] = p.0;
+    #[allow(clippy::cast_possible_truncation)]
+    let grey = ((u16::from(r) + u16::from(g) + u16::from(b)) / 3) as u8;
+    let grey = grey / 4 + 192;
+
+    Rgba([grey, grey, grey, 255])
+}
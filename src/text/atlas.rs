@@ -0,0 +1,143 @@
+//! Packing rendered glyphs into a few large textures.
+
+use sdl2::{
+    pixels::PixelFormatEnum, rect::Rect, render::Canvas, surface::SurfaceRef, video::Window,
+};
+
+use crate::{texture::SharedTexture, CatboxError, Result};
+
+/// The size of each page, unless a glyph is too big to fit.
+const PAGE_SIZE: u32 = 512;
+/// A block of white pixels at the top left of every page, for drawing solid boxes.
+const WHITE_SIZE: u32 = 4;
+/// Empty pixels between glyphs, so scaled text doesn't bleed into its neighbours.
+const PADDING: u32 = 1;
+
+/// Where a glyph ended up in the atlas.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct AtlasRect {
+    pub(crate) page: usize,
+    pub(crate) rect: Rect,
+}
+
+/// Textures that glyphs are added to as they're first drawn, packed in rows.
+#[derive(Default)]
+pub(crate) struct GlyphAtlas {
+    pages: Vec<Page>,
+}
+
+struct Page {
+    texture: SharedTexture,
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    row_height: u32,
+}
+
+impl GlyphAtlas {
+    /// Copy `surf` into the atlas, and return where it went.
+    pub(crate) fn insert(
+        &mut self,
+        canvas: &Canvas<Window>,
+        surf: &SurfaceRef,
+    ) -> Result<AtlasRect> {
+        let (width, height) = (surf.width(), surf.height());
+
+        let fits = self
+            .pages
+            .last_mut()
+            .and_then(|page| page.allocate(width, height));
+        let rect = if let Some(rect) = fits {
+            rect
+        } else {
+            let mut page = Page::new(canvas, width, height)?;
+            let rect = page
+                .allocate(width, height)
+                .ok_or_else(|| CatboxError::Other("glyph is too big for the atlas".to_string()))?;
+            self.pages.push(page);
+            rect
+        };
+        let page = self.pages.len() - 1;
+
+        let surf = surf
+            .convert_format(PixelFormatEnum::ARGB8888)
+            .map_err(CatboxError::sdl)?;
+        let pitch = surf.pitch() as usize;
+        let texture = &mut self.pages[page].texture;
+        surf.with_lock(|pixels| texture.update(rect, pixels, pitch))?;
+
+        Ok(AtlasRect { page, rect })
+    }
+
+    /// A part of every page that is solid white, away from the edges so that scaling it up
+    /// doesn't blend in any other pixels.
+    pub(crate) fn white_rect() -> Rect {
+        Rect::new(1, 1, WHITE_SIZE - 2, WHITE_SIZE - 2)
+    }
+
+    /// Get the texture for a page, tinted with `colour`.
+    pub(crate) fn page(&mut self, page: usize, colour: (u8, u8, u8)) -> &SharedTexture {
+        let texture = &mut self.pages[page].texture;
+        let (r, g, b) = colour;
+        texture.set_color_mod(r, g, b);
+        texture
+    }
+
+    /// Get the first page, tinted with `colour`, for drawing solid boxes with
+    /// [`Self::white_rect()`]. The page is made if no glyphs have been added yet.
+    pub(crate) fn white_page(
+        &mut self,
+        canvas: &Canvas<Window>,
+        colour: (u8, u8, u8),
+    ) -> Result<&SharedTexture> {
+        if self.pages.is_empty() {
+            self.pages.push(Page::new(canvas, 0, 0)?);
+        }
+
+        Ok(self.page(0, colour))
+    }
+}
+
+impl Page {
+    fn new(canvas: &Canvas<Window>, min_width: u32, min_height: u32) -> Result<Self> {
+        let width = PAGE_SIZE.max(min_width + PADDING);
+        let height = PAGE_SIZE.max(min_height + WHITE_SIZE + PADDING * 2);
+        let mut texture = SharedTexture::blank(canvas, width, height)?;
+
+        let white = vec![0xff; (WHITE_SIZE * WHITE_SIZE * 4) as usize];
+        texture.update(
+            Rect::new(0, 0, WHITE_SIZE, WHITE_SIZE),
+            &white,
+            (WHITE_SIZE * 4) as usize,
+        )?;
+
+        Ok(Self {
+            texture,
+            width,
+            height,
+            x: WHITE_SIZE + PADDING,
+            y: 0,
+            row_height: WHITE_SIZE,
+        })
+    }
+
+    /// Find space for a `width` by `height` rectangle, starting a new row if this one is full.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Rect> {
+        if self.x + width > self.width {
+            self.x = 0;
+            self.y += self.row_height + PADDING;
+            self.row_height = 0;
+        }
+        if self.x + width > self.width || self.y + height > self.height {
+            return None;
+        }
+
+        #[allow(clippy::cast_possible_wrap)]
+        let rect = Rect::new(self.x as i32, self.y as i32, width.max(1), height.max(1));
+        self.x += width + PADDING;
+        self.row_height = self.row_height.max(height);
+
+        Some(rect)
+    }
+}
@@ -0,0 +1,309 @@
+//! Fonts drawn from images instead of TTF files.
+
+use sdl2::{
+    image::ImageRWops, pixels::PixelFormatEnum, rect::Rect, render::BlendMode, rwops::RWops,
+    surface::Surface,
+};
+use std::{collections::HashMap, convert::TryFrom, fs, path::Path};
+
+use crate::{CatboxError, Result};
+
+/// A font made of pictures of each character, for crisp pixel art text.
+///
+/// `AngelCode` `BMFont` `.fnt` files (the text format) are loaded automatically the first time
+/// they're used, just like TTF files:
+/// ```no_run
+/// # use cat_box::*;
+/// # let game = Game::new("", 1000, 800);
+/// # game.run(|ctx| {
+/// let mode = TextMode::Transparent { colour: (255, 255, 255) };
+/// draw_text(ctx, "Press start", "pixel.fnt", 32, (500, 400), mode).unwrap();
+/// # });
+/// ```
+/// Other fonts have to be [added to the context](crate::Context::add_bitmap_font()) under a name,
+/// which is then used in place of a path.
+///
+/// The font size is the height of a line that the font should fit in. Bitmap fonts are only ever
+/// scaled by whole numbers so they stay crisp, so they're drawn at the largest scale that fits, or
+/// at their own size if that's too big. Glyphs are tinted with the text's colour, so they should
+/// be white to be drawn in any colour.
+pub struct BitmapFont {
+    pages: Vec<Surface<'static>>,
+    glyphs: HashMap<char, BitmapGlyph>,
+    kerning: HashMap<(char, char), i32>,
+    line_height: u32,
+    base: u32,
+}
+
+/// Where a character is in the font's images, and how to place it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BitmapGlyph {
+    pub(crate) page: usize,
+    pub(crate) rect: Option<Rect>,
+    pub(crate) offset: (i32, i32),
+    pub(crate) advance: i32,
+}
+
+impl BitmapFont {
+    /// Load an `AngelCode` `BMFont` file, in the text format, along with the images it uses.
+    ///
+    /// The paths of the images are relative to the `.fnt` file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let fnt = fs::read_to_string(path).map_err(|e| CatboxError::asset(path, e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+        Self::from_fnt(&fnt, dir).map_err(|e| match e {
+            e @ CatboxError::Asset { .. } => e,
+            e => CatboxError::asset(path, e),
+        })
+    }
+
+    /// Load a font from an image with every character in a grid of `cell_width` by `cell_height`
+    /// cells.
+    ///
+    /// `chars` are the characters in the grid, row by row from the top left.
+    /// ```no_run
+    /// # use cat_box::text::BitmapFont;
+    /// let font = BitmapFont::grid("font.png", 8, 8, " !\"#$%&'()*+,-./0123456789:;<=>?").unwrap();
+    /// ```
+    pub fn grid<P: AsRef<Path>>(
+        path: P,
+        cell_width: u32,
+        cell_height: u32,
+        chars: &str,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let page = load_page(path)?;
+        let columns = (page.width() / cell_width.max(1)).max(1);
+        let advance = i32::try_from(cell_width).unwrap_or(i32::MAX);
+
+        let glyphs = chars
+            .chars()
+            .zip(0..)
+            .map(|(ch, i)| {
+                let (column, row) = (i % columns, i / columns);
+                #[allow(clippy::cast_possible_wrap)]
+                let rect = Rect::new(
+                    (column * cell_width) as i32,
+                    (row * cell_height) as i32,
+                    cell_width,
+                    cell_height,
+                );
+                let glyph = BitmapGlyph {
+                    page: 0,
+                    rect: Some(rect),
+                    offset: (0, 0),
+                    advance,
+                };
+                (ch, glyph)
+            })
+            .collect();
+
+        Ok(Self {
+            pages: vec![page],
+            glyphs,
+            kerning: HashMap::new(),
+            line_height: cell_height,
+            base: cell_height,
+        })
+    }
+
+    /// Move `second` by `amount` pixels whenever it comes right after `first`, negative being
+    /// closer together.
+    ///
+    /// `.fnt` files come with their own kerning pairs, but this can add more.
+    #[must_use]
+    pub fn with_kerning(mut self, first: char, second: char, amount: i32) -> Self {
+        self.kerning.insert((first, second), amount);
+        self
+    }
+
+    /// The height of a line at the font's own size, in pixels.
+    #[must_use]
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    /// How far the baseline is below the top of a line at the font's own size, in pixels.
+    #[must_use]
+    pub fn base(&self) -> u32 {
+        self.base
+    }
+
+    pub(crate) fn glyph(&self, ch: char) -> Option<&BitmapGlyph> {
+        self.glyphs.get(&ch)
+    }
+
+    /// How far the pen moves after drawing `ch` at the font's own size, if the font has it.
+    #[must_use]
+    pub fn advance(&self, ch: char) -> Option<i32> {
+        self.glyph(ch).map(|glyph| glyph.advance)
+    }
+
+    /// How far `second` is moved when it comes right after `first`, negative being closer
+    /// together.
+    #[must_use]
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    /// Copy a glyph out of its page into a surface of its own.
+    pub(crate) fn glyph_surface(&self, glyph: &BitmapGlyph) -> Result<Option<Surface<'static>>> {
+        let (Some(rect), Some(page)) = (glyph.rect, self.pages.get(glyph.page)) else {
+            return Ok(None);
+        };
+
+        let mut surf = Surface::new(rect.width(), rect.height(), PixelFormatEnum::ARGB8888)
+            .map_err(CatboxError::sdl)?;
+        page.blit(rect, &mut surf, None).map_err(CatboxError::sdl)?;
+        Ok(Some(surf))
+    }
+
+    /// Parse the contents of a `.fnt` file, loading the images it uses from `dir`.
+    /// ```
+    /// # use cat_box::text::BitmapFont;
+    /// let fnt = "\
+    /// info face=\"Pixel\" size=8
+    /// common lineHeight=10 base=8 scaleW=64 scaleH=64 pages=0
+    /// chars count=3
+    /// char id=65 x=0 y=0 width=6 height=8 xoffset=0 yoffset=0 xadvance=7 page=0
+    /// char id=86 x=6 y=0 width=6 height=8 xoffset=0 yoffset=1 xadvance=7 page=0
+    /// char id=32 x=0 y=0 width=0 height=0 xoffset=0 yoffset=0 xadvance=4 page=0
+    /// kernings count=1
+    /// kerning first=65 second=86 amount=-1
+    /// ";
+    /// let font = BitmapFont::from_fnt(fnt, "fonts").unwrap();
+    /// assert_eq!(font.line_height(), 10);
+    /// assert_eq!(font.base(), 8);
+    /// assert_eq!(font.advance('A'), Some(7));
+    /// assert_eq!(font.advance(' '), Some(4));
+    /// assert_eq!(font.advance('B'), None);
+    /// assert_eq!(font.kerning('A', 'V'), -1);
+    /// assert_eq!(font.kerning('V', 'A'), 0);
+    ///
+    /// assert!(BitmapFont::from_fnt("common base=8", "fonts").is_err());
+    /// assert!(BitmapFont::from_fnt("char id=65 width=six height=8", "fonts").is_err());
+    /// ```
+    pub fn from_fnt<P: AsRef<Path>>(fnt: &str, dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut pages = Vec::new();
+        let mut glyphs = HashMap::new();
+        let mut kerning = HashMap::new();
+        let (mut line_height, mut base) = (0, 0);
+
+        for line in fnt.lines() {
+            let (tag, attrs) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let attrs = Attributes::parse(attrs);
+
+            match tag {
+                "common" => {
+                    line_height = attrs.number("lineHeight")?;
+                    base = attrs.number("base")?;
+                }
+                "page" => {
+                    let id: usize = attrs.number("id")?;
+                    let file = dir.join(attrs.get("file")?);
+                    if pages.len() <= id {
+                        pages.resize_with(id + 1, || None);
+                    }
+                    pages[id] = Some(load_page(&file)?);
+                }
+                "char" => {
+                    let (width, height) = (attrs.number("width")?, attrs.number("height")?);
+                    let rect = (width > 0 && height > 0).then(|| {
+                        Rect::new(
+                            attrs.number("x").unwrap_or(0),
+                            attrs.number("y").unwrap_or(0),
+                            width,
+                            height,
+                        )
+                    });
+                    let glyph = BitmapGlyph {
+                        page: attrs.number("page")?,
+                        rect,
+                        offset: (attrs.number("xoffset")?, attrs.number("yoffset")?),
+                        advance: attrs.number("xadvance")?,
+                    };
+                    glyphs.insert(attrs.char("id")?, glyph);
+                }
+                "kerning" => {
+                    let pair = (attrs.char("first")?, attrs.char("second")?);
+                    kerning.insert(pair, attrs.number("amount")?);
+                }
+                _ => {}
+            }
+        }
+
+        let pages = pages
+            .into_iter()
+            .enumerate()
+            .map(|(id, page)| {
+                page.ok_or_else(|| CatboxError::Parse(format!("page {id} is missing")))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            pages,
+            glyphs,
+            kerning,
+            line_height,
+            base,
+        })
+    }
+}
+
+/// The `key=value` pairs on a line of a `.fnt` file.
+struct Attributes<'a> {
+    pairs: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Attributes<'a> {
+    fn parse(mut s: &'a str) -> Self {
+        let mut pairs = Vec::new();
+        while let Some((key, rest)) = s.trim_start().split_once('=') {
+            let (value, rest) = match rest.strip_prefix('"') {
+                Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+                None => rest.split_once(' ').unwrap_or((rest, "")),
+            };
+            pairs.push((key.trim(), value));
+            s = rest;
+        }
+
+        Self { pairs }
+    }
+
+    fn get(&self, key: &str) -> Result<&'a str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| CatboxError::Parse(format!("missing `{key}`")))
+    }
+
+    fn number<T: std::str::FromStr>(&self, key: &str) -> Result<T> {
+        let value = self.get(key)?;
+        value
+            .parse()
+            .map_err(|_| CatboxError::Parse(format!("`{key}` should be a number, got `{value}`")))
+    }
+
+    fn char(&self, key: &str) -> Result<char> {
+        let id: u32 = self.number(key)?;
+        char::from_u32(id)
+            .ok_or_else(|| CatboxError::Parse(format!("`{key}={id}` isn't a character")))
+    }
+}
+
+/// Load an image for a bitmap font, without blending so it can be copied from exactly.
+fn load_page(path: &Path) -> Result<Surface<'static>> {
+    let bytes = fs::read(path).map_err(|e| CatboxError::asset(path, e))?;
+    let mut page = RWops::from_bytes(&bytes)
+        .map_err(CatboxError::sdl)?
+        .load()
+        .map_err(|e| CatboxError::asset(path, CatboxError::sdl(e)))?;
+    page.set_blend_mode(BlendMode::None)
+        .map_err(CatboxError::sdl)?;
+
+    Ok(page)
+}
@@ -0,0 +1,397 @@
+//! Breaking text into lines and placing them in a box.
+
+use std::{
+    convert::TryFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use super::CachedFont;
+
+/// How each line is lined up horizontally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+/// How the text is lined up vertically inside of its [bounds](TextLayout::with_bounds()).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// The point of the text box that is placed at the position it's drawn at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+    /// The left end of the first line's baseline, which the bottoms of most letters sit on.
+    BaselineLeft,
+    /// The middle of the first line's baseline.
+    Baseline,
+    /// The right end of the first line's baseline.
+    BaselineRight,
+}
+
+impl Anchor {
+    /// How far along the box the anchor is horizontally, from `0.0` to `1.0`.
+    fn x(self) -> f64 {
+        match self {
+            Self::TopLeft | Self::Left | Self::BottomLeft | Self::BaselineLeft => 0.0,
+            Self::Top | Self::Center | Self::Bottom | Self::Baseline => 0.5,
+            Self::TopRight | Self::Right | Self::BottomRight | Self::BaselineRight => 1.0,
+        }
+    }
+}
+
+/// How to lay out and draw a block of text.
+///
+/// Text is broken into lines at every `\n`, and also between words if it has a
+/// [wrap width](Self::with_wrap_width()). Draw it with
+/// [`draw_text_layout()`](crate::draw_text_layout()), or get its size with
+/// [`measure_text()`](crate::measure_text()).
+/// ```no_run
+/// # use cat_box::{Game, TextMode, draw_text_layout, text::{Align, Anchor, TextLayout, VerticalAlign}};
+/// # let game = Game::new("text demo", 1000, 800);
+/// let dialog = TextLayout::new("MesloLGS NF Regular.ttf", 24)
+///     .with_bounds(400, 150)
+///     .with_align(Align::Center)
+///     .with_vertical_align(VerticalAlign::Middle)
+///     .with_line_spacing(1.2)
+///     .with_anchor(Anchor::Bottom);
+///
+/// game.run(|ctx| {
+///     let mode = TextMode::Transparent { colour: (255, 255, 255) };
+///     draw_text_layout(ctx, "It's dangerous to go alone! Take this.", (500, 780), &dialog, mode)
+///         .unwrap();
+/// })
+/// .unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    font: PathBuf,
+    size: u16,
+    wrap_width: Option<u32>,
+    height: Option<u32>,
+    align: Align,
+    vertical_align: VerticalAlign,
+    line_spacing: f32,
+    anchor: Anchor,
+}
+
+impl TextLayout {
+    /// Lay text out in the font at `font`, in `size`, without wrapping.
+    ///
+    /// By default, lines are left aligned and `pos` is the top left of the text.
+    pub fn new<P: Into<PathBuf>>(font: P, size: u16) -> Self {
+        Self {
+            font: font.into(),
+            size,
+            wrap_width: None,
+            height: None,
+            align: Align::Left,
+            vertical_align: VerticalAlign::Top,
+            line_spacing: 1.0,
+            anchor: Anchor::TopLeft,
+        }
+    }
+
+    /// Break lines between words so that none of them are wider than `width`.
+    ///
+    /// Words that don't fit on a line by themselves are broken between letters.
+    #[must_use]
+    pub fn with_wrap_width(mut self, width: u32) -> Self {
+        self.wrap_width = Some(width);
+        self
+    }
+
+    /// Fit the text in a `width` by `height` box, wrapping it at `width`.
+    ///
+    /// The text is aligned inside of the box, and the [anchor](Self::with_anchor()) is a point on
+    /// the box. Text that doesn't fit vertically spills out of it.
+    #[must_use]
+    pub fn with_bounds(mut self, width: u32, height: u32) -> Self {
+        self.wrap_width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    /// Set how each line is lined up horizontally.
+    #[must_use]
+    pub fn with_align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Set how the text is lined up vertically inside of its bounds.
+    #[must_use]
+    pub fn with_vertical_align(mut self, align: VerticalAlign) -> Self {
+        self.vertical_align = align;
+        self
+    }
+
+    /// Set the space between lines, `1.0` being the font's normal line spacing.
+    #[must_use]
+    pub fn with_line_spacing(mut self, spacing: f32) -> Self {
+        self.line_spacing = spacing;
+        self
+    }
+
+    /// Set which point of the text box is placed at the position it's drawn at.
+    #[must_use]
+    pub fn with_anchor(mut self, anchor: Anchor) -> Self {
+        self.anchor = anchor;
+        self
+    }
+
+    /// The font the text is drawn in.
+    #[must_use]
+    pub fn font(&self) -> &Path {
+        &self.font
+    }
+
+    /// The size of the font.
+    #[must_use]
+    pub fn size(&self) -> u16 {
+        self.size
+    }
+
+    /// Break `text` into lines the way it's drawn, with `width` giving how wide a piece of it is.
+    ///
+    /// Spaces at the end of each line are left out.
+    /// ```
+    /// # use cat_box::text::TextLayout;
+    /// // Every character is 10 pixels wide.
+    /// let width = |text: &str| 10 * text.chars().count() as u32;
+    ///
+    /// let layout = TextLayout::new("font.ttf", 16).with_wrap_width(100);
+    /// assert_eq!(
+    ///     layout.split_lines("It's dangerous to go alone!", width),
+    ///     ["It's", "dangerous", "to go", "alone!"]
+    /// );
+    /// // Spaces at the end of a line don't make it wrap.
+    /// assert_eq!(layout.split_lines("Take this.       ", width), ["Take this."]);
+    /// // Words wider than a line are broken between letters.
+    /// assert_eq!(layout.split_lines("Aaaaaaaaaaaaaah", width), ["Aaaaaaaaaa", "aaaah"]);
+    ///
+    /// let layout = TextLayout::new("font.ttf", 16);
+    /// assert_eq!(layout.split_lines("one line\r\nanother", width), ["one line", "another"]);
+    /// ```
+    pub fn split_lines<'a, F: Fn(&str) -> u32>(&self, text: &'a str, width: F) -> Vec<&'a str> {
+        break_lines(text, self.wrap_width, &|range: Range<usize>| {
+            width(&text[range])
+        })
+        .into_iter()
+        .map(|(range, _)| &text[range])
+        .collect()
+    }
+
+    /// Break `text` into lines and place them relative to the anchor.
+    pub(crate) fn lay_out(&self, font: &CachedFont, text: &str) -> Vec<Line> {
+        self.lay_out_with(font, text, |range| font.width(&text[range]))
+    }
+
+    /// Like [`lay_out()`](Self::lay_out()), but with `width` giving the width of each part of
+    /// `text`, for text that isn't all drawn in `font`.
+    ///
+    /// The height of the lines still comes from `font`.
+    pub(crate) fn lay_out_with<F: Fn(Range<usize>) -> u32>(
+        &self,
+        font: &CachedFont,
+        text: &str,
+        width: F,
+    ) -> Vec<Line> {
+        let lines = break_lines(text, self.wrap_width, &width);
+
+        let advance = self.line_advance(font);
+        let text_height = text_height(lines.len(), font.height(), advance);
+        let text_width = lines.iter().map(|(_, width)| *width).max().unwrap_or(0);
+
+        let box_width = f64::from(self.wrap_width.unwrap_or(text_width));
+        let box_height = f64::from(self.height.unwrap_or(text_height));
+
+        let top = match self.vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (box_height - f64::from(text_height)) / 2.0,
+            VerticalAlign::Bottom => box_height - f64::from(text_height),
+        };
+        let anchor_y = match self.anchor {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0.0,
+            Anchor::Left | Anchor::Center | Anchor::Right => box_height / 2.0,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => box_height,
+            Anchor::BaselineLeft | Anchor::Baseline | Anchor::BaselineRight => {
+                top + f64::from(font.ascent())
+            }
+        };
+        let anchor_x = box_width * self.anchor.x();
+
+        lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, (range, width))| {
+                let x = match self.align {
+                    Align::Left => 0.0,
+                    Align::Center => (box_width - f64::from(width)) / 2.0,
+                    Align::Right => box_width - f64::from(width),
+                };
+                #[allow(clippy::cast_precision_loss)]
+                let y = top + f64::from(advance) * i as f64;
+
+                #[allow(clippy::cast_possible_truncation)]
+                let (x, y) = ((x - anchor_x).round() as i32, (y - anchor_y).round() as i32);
+                Line { range, x, y, width }
+            })
+            .collect()
+    }
+
+    /// The width of the widest line and the height of all of them.
+    pub(crate) fn measure(&self, font: &CachedFont, text: &str) -> (u32, u32) {
+        self.measure_with(font, text, |range| font.width(&text[range]))
+    }
+
+    /// Like [`measure()`](Self::measure()), with the widths given by `width`.
+    pub(crate) fn measure_with<F: Fn(Range<usize>) -> u32>(
+        &self,
+        font: &CachedFont,
+        text: &str,
+        width: F,
+    ) -> (u32, u32) {
+        let lines = break_lines(text, self.wrap_width, &width);
+        let width = lines.iter().map(|(_, width)| *width).max().unwrap_or(0);
+        let height = text_height(lines.len(), font.height(), self.line_advance(font));
+
+        (width, height)
+    }
+
+    /// How far apart the tops of each line are.
+    fn line_advance(&self, font: &CachedFont) -> u32 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let advance = (f64::from(font.line_skip()) * f64::from(self.line_spacing))
+            .round()
+            .max(0.0) as u32;
+        advance
+    }
+}
+
+/// A line of laid out text.
+pub(crate) struct Line {
+    /// Where the line is in the text.
+    pub(crate) range: Range<usize>,
+    /// The left of the line, relative to the anchor.
+    pub(crate) x: i32,
+    /// The top of the line, relative to the anchor.
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+}
+
+/// The height of `lines` lines, each `advance` below the last.
+fn text_height(lines: usize, line_height: u32, advance: u32) -> u32 {
+    match u32::try_from(lines) {
+        Ok(0) => 0,
+        Ok(lines) => line_height + advance * (lines - 1),
+        Err(_) => u32::MAX,
+    }
+}
+
+/// Split `text` at newlines, and between words to keep lines narrower than `max_width`.
+fn break_lines<F: Fn(Range<usize>) -> u32>(
+    text: &str,
+    max_width: Option<u32>,
+    width: &F,
+) -> Vec<(Range<usize>, u32)> {
+    let mut lines = Vec::new();
+
+    let mut paragraph_start = 0;
+    for paragraph in text.split('\n') {
+        let offset = paragraph_start;
+        paragraph_start += paragraph.len() + 1;
+
+        let paragraph = paragraph.trim_end_matches('\r');
+        let Some(max_width) = max_width else {
+            let range = offset..offset + paragraph.len();
+            lines.push((range.clone(), width(range)));
+            continue;
+        };
+
+        // The line being built is `text[start..end]`.
+        let mut start = offset;
+        let mut end = offset;
+        for (word_start, word) in words(paragraph) {
+            let word_end = offset + word_start + word.len();
+            if width(start..word_end) <= max_width {
+                end = word_end;
+                continue;
+            }
+
+            // Spaces at the end of a line are dropped anyway.
+            let trimmed = word.trim_start();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            if end > start {
+                lines.push(line(text, start..end, width));
+            }
+            start = word_end - trimmed.len();
+            end = start;
+
+            // Break words that are too long to fit on a line of their own between letters.
+            for (i, ch) in trimmed.char_indices() {
+                let ch_end = word_end - trimmed.len() + i + ch.len_utf8();
+                if end > start && width(start..ch_end) > max_width {
+                    lines.push(line(text, start..end, width));
+                    start = end;
+                }
+                end = ch_end;
+            }
+        }
+        lines.push(line(text, start..end, width));
+    }
+
+    lines
+}
+
+/// A line without any trailing spaces, and its width.
+fn line<F: Fn(Range<usize>) -> u32>(
+    text: &str,
+    range: Range<usize>,
+    width: &F,
+) -> (Range<usize>, u32) {
+    let trimmed = text[range.clone()].trim_end();
+    let range = range.start..range.start + trimmed.len();
+    (range.clone(), width(range))
+}
+
+/// The words in `text` and where they start, keeping any spaces before them.
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut start = 0;
+    let mut in_word = false;
+    let mut boundaries = Vec::new();
+    for (i, ch) in text.char_indices() {
+        if ch.is_whitespace() {
+            if in_word {
+                boundaries.push((start, &text[start..i]));
+                start = i;
+            }
+            in_word = false;
+        } else {
+            in_word = true;
+        }
+    }
+    if start < text.len() {
+        boundaries.push((start, &text[start..]));
+    }
+
+    boundaries.into_iter()
+}
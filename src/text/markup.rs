@@ -0,0 +1,351 @@
+//! Text with inline colours, styles and icons.
+
+use sdl2::{pixels::Color, rect::Rect, ttf::FontStyle};
+use std::{collections::HashMap, convert::TryFrom, ops::Range, str::FromStr};
+
+use super::TextLayout;
+use crate::{math::vec2::Vec2Int, objects::sprite::Sprite, CatboxError, Context, Result, TextMode};
+
+/// What an icon is replaced with in the [plain text](RichText::plain_text()).
+pub const ICON_CHAR: char = '\u{fffc}';
+
+/// Text made up of parts in different colours and styles, with sprites in between them.
+///
+/// It's written with tags in square brackets:
+/// - `[color=red]...[/color]` draws the text in between in a colour, either by name or as
+///   `#rrggbb`. `colour` works too. Text outside of any colour uses the [`TextMode`] it's drawn
+///   with.
+/// - `[b]...[/b]` makes text bold, and `[i]...[/i]` makes it italic.
+/// - `[icon=name]` draws the sprite added with [`with_icon()`](Self::with_icon()), scaled to the
+///   height of a line.
+/// - `[[` is a `[` that doesn't start a tag.
+///
+/// Tags can be nested, and any still open at the end are closed. Draw it with
+/// [`draw_rich_text()`](crate::draw_rich_text()).
+/// ```no_run
+/// # use cat_box::{Game, TextMode, draw_rich_text, objects::sprite::Sprite, text::{RichText, TextLayout}};
+/// # let game = Game::new("rich text demo", 1000, 800);
+/// let coin = Sprite::new("coin.png", 0, 0).unwrap();
+/// let mut price = RichText::new("Costs [b][color=#ffd700]50[/color][/b] [icon=coin], [i]sale![/i]")
+///     .unwrap()
+///     .with_icon("coin", coin);
+/// let layout = TextLayout::new("MesloLGS NF Regular.ttf", 24);
+///
+/// game.run(|ctx| {
+///     let mode = TextMode::Transparent { colour: (255, 255, 255) };
+///     draw_rich_text(ctx, &mut price, (20, 20), &layout, mode).unwrap();
+/// })
+/// .unwrap();
+/// ```
+pub struct RichText {
+    text: String,
+    spans: Vec<Span>,
+    icons: HashMap<String, Sprite>,
+}
+
+/// A part of the text that is all drawn the same way.
+#[derive(Clone, Debug, PartialEq)]
+struct Span {
+    range: Range<usize>,
+    colour: Option<(u8, u8, u8)>,
+    style: FontStyle,
+    /// The name of the icon, which takes up a single [`ICON_CHAR`].
+    icon: Option<String>,
+}
+
+impl RichText {
+    /// Parse `markup`, failing if it has tags that are unknown or closed without being opened.
+    /// ```
+    /// # use cat_box::text::{RichText, ICON_CHAR};
+    /// let text = RichText::new("[b]Hi [color=#FF8000]there[/color][/b], [[not a tag]").unwrap();
+    /// assert_eq!(text.plain_text(), "Hi there, [not a tag]");
+    ///
+    /// // Tags that are still open at the end are closed, and icons take up one character.
+    /// let text = RichText::new("[i][colour=red]50 [icon=coin]").unwrap();
+    /// assert_eq!(text.plain_text(), format!("50 {ICON_CHAR}"));
+    ///
+    /// assert!(RichText::new("[b]bold[/i]").is_err());
+    /// assert!(RichText::new("[/color]").is_err());
+    /// assert!(RichText::new("[u]underlined[/u]").is_err());
+    /// assert!(RichText::new("[color=#ff80]short[/color]").is_err());
+    /// assert!(RichText::new("[color=#gg0000]not hex[/color]").is_err());
+    /// assert!(RichText::new("[color=chartreuse]unknown[/color]").is_err());
+    /// assert!(RichText::new("[b unclosed").is_err());
+    /// ```
+    pub fn new(markup: &str) -> Result<Self> {
+        let mut parser = Parser::default();
+
+        let mut rest = markup;
+        while let Some(open) = rest.find('[') {
+            parser.text.push_str(&rest[..open]);
+            rest = &rest[open + 1..];
+
+            if let Some(after) = rest.strip_prefix('[') {
+                parser.text.push('[');
+                rest = after;
+                continue;
+            }
+
+            let close = rest
+                .find(']')
+                .ok_or_else(|| CatboxError::Parse(format!("unclosed tag `[{rest}`")))?;
+            parser.tag(rest[..close].trim())?;
+            rest = &rest[close + 1..];
+        }
+        parser.text.push_str(rest);
+        parser.flush();
+
+        Ok(Self {
+            text: parser.text,
+            spans: parser.spans,
+            icons: HashMap::new(),
+        })
+    }
+
+    /// Use `sprite` for every `[icon=name]` in the text.
+    #[must_use]
+    pub fn with_icon<S: Into<String>>(mut self, name: S, sprite: Sprite) -> Self {
+        self.icons.insert(name.into(), sprite);
+        self
+    }
+
+    /// The text without any tags, with each icon replaced by [`ICON_CHAR`].
+    #[must_use]
+    pub fn plain_text(&self) -> &str {
+        &self.text
+    }
+
+    fn icon(&self, name: &str) -> Result<&Sprite> {
+        self.icons.get(name).ok_or_else(|| missing_icon(name))
+    }
+
+    /// How far along its line the end of each byte of the text is, when it's all on one line.
+    ///
+    /// The width of `text[a..b]` is `widths[b] - widths[a]`, as long as both are on character
+    /// boundaries.
+    fn widths(&self, ctx: &mut Context, layout: &TextLayout) -> Result<Vec<u32>> {
+        let (font, size) = (layout.font(), layout.size());
+        let height = ctx.fonts.get(&ctx.ttf_subsystem, font, size)?.height();
+
+        let mut widths = vec![0; self.text.len() + 1];
+        let mut total = 0;
+        for span in &self.spans {
+            if let Some(name) = &span.icon {
+                total += icon_width(self.icon(name)?, height);
+                widths[span.range.end] = total;
+                continue;
+            }
+
+            let font = ctx
+                .fonts
+                .get_styled(&ctx.ttf_subsystem, font, size, span.style)?;
+            let mut prev = None;
+            for (i, ch) in self.text[span.range.clone()].char_indices() {
+                let kerning = prev.map_or(0, |prev| font.kerning(prev, ch));
+                prev = Some(ch);
+                total += u32::try_from(font.advance(ch) + kerning).unwrap_or(0);
+                widths[span.range.start + i + ch.len_utf8()] = total;
+            }
+        }
+
+        Ok(widths)
+    }
+
+    /// The width of the widest line and the height of all of them.
+    pub(crate) fn measure(&self, ctx: &mut Context, layout: &TextLayout) -> Result<(u32, u32)> {
+        let widths = self.widths(ctx, layout)?;
+        let font = ctx
+            .fonts
+            .get(&ctx.ttf_subsystem, layout.font(), layout.size())?;
+
+        Ok(layout.measure_with(font, &self.text, |range| {
+            widths[range.end] - widths[range.start]
+        }))
+    }
+
+    pub(crate) fn draw(
+        &mut self,
+        ctx: &mut Context,
+        pos: Vec2Int,
+        layout: &TextLayout,
+        mode: TextMode,
+    ) -> Result<()> {
+        if self.text.is_empty() {
+            return Ok(());
+        }
+
+        let widths = self.widths(ctx, layout)?;
+        let (font, size) = (layout.font(), layout.size());
+        let regular = ctx.fonts.get(&ctx.ttf_subsystem, font, size)?;
+        let height = regular.height();
+        let lines = layout.lay_out_with(regular, &self.text, |range| {
+            widths[range.end] - widths[range.start]
+        });
+
+        let (default_colour, background) = mode.colours();
+        for line in lines {
+            for span in &self.spans {
+                let start = span.range.start.max(line.range.start);
+                let end = span.range.end.min(line.range.end);
+                if start >= end {
+                    continue;
+                }
+
+                let x = i32::try_from(widths[start] - widths[line.range.start]).unwrap_or(0);
+                let width = widths[end] - widths[start];
+                let rect = Rect::new(pos.x + line.x + x, pos.y + line.y, width, height);
+                let (dest_rect, angle) = ctx.project(rect, 0.0);
+
+                if let Some(name) = &span.icon {
+                    if let Some(background) = background {
+                        let regular = ctx.fonts.get(&ctx.ttf_subsystem, font, size)?;
+                        regular.draw(
+                            &mut ctx.canvas,
+                            "",
+                            dest_rect,
+                            angle,
+                            background,
+                            Some(background),
+                        )?;
+                    }
+                    let sprite = self.icons.get_mut(name).ok_or_else(|| missing_icon(name))?;
+                    sprite.draw_in(ctx, rect, 0.0)?;
+                    continue;
+                }
+
+                let colour = span
+                    .colour
+                    .map_or(default_colour, |(r, g, b)| Color::RGB(r, g, b));
+                let font = ctx
+                    .fonts
+                    .get_styled(&ctx.ttf_subsystem, font, size, span.style)?;
+                let text = &self.text[start..end];
+                font.draw(&mut ctx.canvas, text, dest_rect, angle, colour, background)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for RichText {
+    type Err = CatboxError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::new(s)
+    }
+}
+
+/// The state of the markup while it's being parsed.
+#[derive(Default)]
+struct Parser {
+    text: String,
+    spans: Vec<Span>,
+    /// Where the span that hasn't been pushed yet starts.
+    start: usize,
+    colours: Vec<(u8, u8, u8)>,
+    bold: usize,
+    italic: usize,
+}
+
+impl Parser {
+    fn tag(&mut self, tag: &str) -> Result<()> {
+        // Everything up to here is drawn the way it was before the tag.
+        self.flush();
+
+        let unopened = |name: &str| CatboxError::Parse(format!("`[/{name}]` without a `[{name}]`"));
+        match tag.split_once('=') {
+            Some(("color" | "colour", colour)) => self.colours.push(parse_colour(colour.trim())?),
+            Some(("icon", name)) => {
+                self.text.push(ICON_CHAR);
+                self.spans.push(Span {
+                    range: self.start..self.text.len(),
+                    colour: None,
+                    style: FontStyle::NORMAL,
+                    icon: Some(name.trim().to_string()),
+                });
+                self.start = self.text.len();
+            }
+            Some(_) => return Err(CatboxError::Parse(format!("unknown tag `[{tag}]`"))),
+            None => match tag {
+                "b" => self.bold += 1,
+                "i" => self.italic += 1,
+                "/b" => self.bold = self.bold.checked_sub(1).ok_or_else(|| unopened("b"))?,
+                "/i" => self.italic = self.italic.checked_sub(1).ok_or_else(|| unopened("i"))?,
+                "/color" | "/colour" => {
+                    self.colours.pop().ok_or_else(|| unopened(&tag[1..]))?;
+                }
+                _ => return Err(CatboxError::Parse(format!("unknown tag `[{tag}]`"))),
+            },
+        }
+
+        Ok(())
+    }
+
+    /// End the current span at the end of the text.
+    fn flush(&mut self) {
+        if self.start == self.text.len() {
+            return;
+        }
+
+        let mut style = FontStyle::NORMAL;
+        if self.bold > 0 {
+            style |= FontStyle::BOLD;
+        }
+        if self.italic > 0 {
+            style |= FontStyle::ITALIC;
+        }
+
+        self.spans.push(Span {
+            range: self.start..self.text.len(),
+            colour: self.colours.last().copied(),
+            style,
+            icon: None,
+        });
+        self.start = self.text.len();
+    }
+}
+
+/// Parse a colour name, or a hex colour like `#ff8000`.
+fn parse_colour(s: &str) -> Result<(u8, u8, u8)> {
+    let colour = match s.to_ascii_lowercase().as_str() {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "grey" | "gray" => (128, 128, 128),
+        "red" => (255, 0, 0),
+        "green" => (0, 255, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" => (0, 255, 255),
+        "magenta" => (255, 0, 255),
+        "orange" => (255, 165, 0),
+        "purple" => (128, 0, 128),
+        _ => {
+            let hex = s
+                .strip_prefix('#')
+                .filter(|hex| hex.len() == 6 && hex.is_ascii());
+            let channel = |i| hex.and_then(|hex| u8::from_str_radix(&hex[i..i + 2], 16).ok());
+            match (channel(0), channel(2), channel(4)) {
+                (Some(r), Some(g), Some(b)) => (r, g, b),
+                _ => return Err(CatboxError::Parse(format!("unknown colour `{s}`"))),
+            }
+        }
+    };
+
+    Ok(colour)
+}
+
+fn missing_icon(name: &str) -> CatboxError {
+    CatboxError::Parse(format!("no sprite was added for `[icon={name}]`"))
+}
+
+/// The width of `sprite` when it's scaled to be `height` tall.
+fn icon_width(sprite: &Sprite, height: u32) -> u32 {
+    let (width, sprite_height) = sprite.source_size();
+    if sprite_height == 0 {
+        return 0;
+    }
+
+    let width = u64::from(width) * u64::from(height) / u64::from(sprite_height);
+    u32::try_from(width).unwrap_or(u32::MAX)
+}
@@ -0,0 +1,351 @@
+//! Laying out text in boxes, with wrapping and alignment.
+//!
+//! See [`TextLayout`] for the options, and [`draw_text_layout()`](crate::draw_text_layout()) for
+//! drawing it. [`RichText`] can also have parts in different colours and styles, and icons.
+//!
+//! Text can be drawn with TTF fonts, or with [`BitmapFont`]s for pixel art. Fonts are only loaded
+//! once for each size, and every glyph is rendered the first time it's drawn and copied into a
+//! texture shared by the whole font. After the first frame, drawing text is just
+//! copying rectangles.
+
+mod atlas;
+mod bitmap;
+mod layout;
+mod markup;
+
+pub use bitmap::BitmapFont;
+pub use layout::{Align, Anchor, TextLayout, VerticalAlign};
+pub use markup::{RichText, ICON_CHAR};
+
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::Canvas,
+    ttf::{Font, FontStyle, Sdl2TtfContext},
+    video::Window,
+};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{CatboxError, Result};
+use atlas::{AtlasRect, GlyphAtlas};
+
+/// Every font that has been used, for each size and style it was used in.
+#[derive(Default)]
+pub(crate) struct FontCache {
+    fonts: HashMap<PathBuf, HashMap<(u16, FontStyle), CachedFont>>,
+    /// Bitmap fonts, by the name they were added under or the path they were loaded from.
+    bitmaps: HashMap<PathBuf, Rc<BitmapFont>>,
+}
+
+impl FontCache {
+    /// Get the font at `path` in `size`, loading it if this is the first time it's used.
+    pub(crate) fn get(
+        &mut self,
+        ttf: &Sdl2TtfContext,
+        path: &Path,
+        size: u16,
+    ) -> Result<&mut CachedFont> {
+        self.get_styled(ttf, path, size, FontStyle::NORMAL)
+    }
+
+    /// Get the font at `path` in `size`, made bold or italic by `style`.
+    ///
+    /// Bitmap fonts can't be styled, so `style` is ignored for them.
+    pub(crate) fn get_styled(
+        &mut self,
+        ttf: &Sdl2TtfContext,
+        path: &Path,
+        size: u16,
+        style: FontStyle,
+    ) -> Result<&mut CachedFont> {
+        if !self.bitmaps.contains_key(path) && path.extension() == Some(OsStr::new("fnt")) {
+            let font = BitmapFont::load(path)?;
+            self.bitmaps.insert(path.to_path_buf(), Rc::new(font));
+        }
+        let bitmap = self.bitmaps.get(path);
+
+        let key = (size, bitmap.map_or(style, |_| FontStyle::NORMAL));
+        let loaded = self
+            .fonts
+            .get(path)
+            .is_some_and(|variants| variants.contains_key(&key));
+        if !loaded {
+            let font = match bitmap {
+                Some(bitmap) => CachedFont::bitmap(Rc::clone(bitmap), size),
+                None => CachedFont::load(ttf, path, size, style)?,
+            };
+            self.fonts
+                .entry(path.to_path_buf())
+                .or_default()
+                .insert(key, font);
+        }
+
+        Ok(self
+            .fonts
+            .get_mut(path)
+            .and_then(|variants| variants.get_mut(&key))
+            .expect("font was just loaded"))
+    }
+
+    /// Use `font` wherever `name` is given as a font, replacing what was there before.
+    pub(crate) fn add_bitmap(&mut self, name: PathBuf, font: BitmapFont) {
+        self.fonts.remove(&name);
+        self.bitmaps.insert(name, Rc::new(font));
+    }
+}
+
+/// A glyph that has been rendered into the atlas.
+#[derive(Clone, Copy, Debug)]
+struct Glyph {
+    /// Where the glyph is, or `None` if it has nothing to draw.
+    atlas: Option<AtlasRect>,
+    /// Where the glyph's top left is drawn, relative to the pen and the top of the line.
+    offset: (i32, i32),
+    advance: i32,
+}
+
+/// Where a font's glyphs come from.
+enum Source {
+    // This is really borrowing the `Sdl2TtfContext` on the `Context`, which is dropped after the
+    // `FontCache`.
+    Ttf(Font<'static, 'static>),
+    Bitmap(Rc<BitmapFont>),
+}
+
+/// A font at one size, with the glyphs that have been drawn with it so far.
+///
+/// All sizes are in pixels on the screen. Glyphs in the atlas are at the font's own size, which
+/// is `scale` times smaller.
+pub(crate) struct CachedFont {
+    source: Source,
+    scale: u32,
+    glyphs: HashMap<char, Glyph>,
+    atlas: GlyphAtlas,
+}
+
+impl CachedFont {
+    fn load(ttf: &Sdl2TtfContext, path: &Path, size: u16, style: FontStyle) -> Result<Self> {
+        let mut font = ttf
+            .load_font(path, size)
+            .map_err(|e| CatboxError::asset(path, CatboxError::Ttf(e.into())))?;
+        font.set_style(style);
+        // SAFETY: the font only borrows the TTF context to make sure `TTF_Quit()` isn't called
+        // while it's open. The `Context` drops its `FontCache` before its `Sdl2TtfContext`.
+        let font =
+            unsafe { std::mem::transmute::<Font<'_, 'static>, Font<'static, 'static>>(font) };
+
+        Ok(Self {
+            source: Source::Ttf(font),
+            scale: 1,
+            glyphs: HashMap::new(),
+            atlas: GlyphAtlas::default(),
+        })
+    }
+
+    /// Use a bitmap font at the biggest whole number scale that's no taller than `size`.
+    fn bitmap(font: Rc<BitmapFont>, size: u16) -> Self {
+        let scale = (u32::from(size) / font.line_height().max(1)).max(1);
+
+        Self {
+            source: Source::Bitmap(font),
+            scale,
+            glyphs: HashMap::new(),
+            atlas: GlyphAtlas::default(),
+        }
+    }
+
+    fn scaled(&self, n: i32) -> i32 {
+        n.saturating_mul(i32::try_from(self.scale).unwrap_or(i32::MAX))
+    }
+
+    /// The height of a line of text, in pixels.
+    pub(crate) fn height(&self) -> u32 {
+        match &self.source {
+            Source::Ttf(font) => u32::try_from(font.height()).unwrap_or(0),
+            Source::Bitmap(font) => font.line_height() * self.scale,
+        }
+    }
+
+    /// How far the baseline is below the top of a line, in pixels.
+    pub(crate) fn ascent(&self) -> i32 {
+        match &self.source {
+            Source::Ttf(font) => font.ascent(),
+            Source::Bitmap(font) => self.scaled(i32::try_from(font.base()).unwrap_or(i32::MAX)),
+        }
+    }
+
+    /// The recommended distance between the tops of two lines, in pixels.
+    pub(crate) fn line_skip(&self) -> i32 {
+        match &self.source {
+            Source::Ttf(font) => font.recommended_line_spacing(),
+            Source::Bitmap(_) => i32::try_from(self.height()).unwrap_or(i32::MAX),
+        }
+    }
+
+    /// How far the pen moves after drawing `ch` at the font's own size.
+    fn unscaled_advance(&self, ch: char) -> i32 {
+        if let Some(glyph) = self.glyphs.get(&ch) {
+            return glyph.advance;
+        }
+
+        match &self.source {
+            Source::Ttf(font) => font
+                .find_glyph_metrics(ch)
+                .map_or(0, |metrics| metrics.advance),
+            Source::Bitmap(font) => font.glyph(ch).map_or(0, |glyph| glyph.advance),
+        }
+    }
+
+    /// How far the pen moves after drawing `ch`.
+    pub(crate) fn advance(&self, ch: char) -> i32 {
+        self.scaled(self.unscaled_advance(ch))
+    }
+
+    /// How much further the pen moves when `second` comes right after `first`, at the font's
+    /// own size.
+    fn unscaled_kerning(&self, first: char, second: char) -> i32 {
+        match &self.source {
+            Source::Ttf(_) => 0,
+            Source::Bitmap(font) => font.kerning(first, second),
+        }
+    }
+
+    /// How much further the pen moves when `second` comes right after `first`.
+    pub(crate) fn kerning(&self, first: char, second: char) -> i32 {
+        self.scaled(self.unscaled_kerning(first, second))
+    }
+
+    /// The width of `text` on one line, in pixels.
+    pub(crate) fn width(&self, text: &str) -> u32 {
+        let mut width = 0;
+        let mut prev = None;
+        for ch in text.chars() {
+            width += self.advance(ch) + prev.map_or(0, |prev| self.kerning(prev, ch));
+            prev = Some(ch);
+        }
+        u32::try_from(width).unwrap_or(0)
+    }
+
+    /// Get a glyph, rendering it into the atlas if this is the first time it's used.
+    fn glyph(&mut self, canvas: &Canvas<Window>, ch: char) -> Result<Glyph> {
+        if let Some(&glyph) = self.glyphs.get(&ch) {
+            return Ok(glyph);
+        }
+
+        let glyph = match &self.source {
+            Source::Ttf(font) => {
+                // Zero width glyphs fail to render, but there's nothing to draw for them anyway.
+                let atlas = match font.render_char(ch).blended(Color::WHITE) {
+                    Ok(surf) if surf.width() > 0 && surf.height() > 0 => {
+                        Some(self.atlas.insert(canvas, &surf)?)
+                    }
+                    _ => None,
+                };
+                Glyph {
+                    atlas,
+                    offset: (0, 0),
+                    advance: self.unscaled_advance(ch),
+                }
+            }
+            Source::Bitmap(font) => match font.glyph(ch) {
+                Some(bitmap) => {
+                    let atlas = match font.glyph_surface(bitmap)? {
+                        Some(surf) => Some(self.atlas.insert(canvas, &surf)?),
+                        None => None,
+                    };
+                    Glyph {
+                        atlas,
+                        offset: bitmap.offset,
+                        advance: bitmap.advance,
+                    }
+                }
+                None => Glyph {
+                    atlas: None,
+                    offset: (0, 0),
+                    advance: 0,
+                },
+            },
+        };
+        self.glyphs.insert(ch, glyph);
+
+        Ok(glyph)
+    }
+
+    /// Draw `text` on one line, stretched to fill `dest` and rotated `angle` degrees clockwise
+    /// around its center.
+    ///
+    /// With a `background`, the whole of `dest` is filled in first.
+    pub(crate) fn draw(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        text: &str,
+        dest: Rect,
+        angle: f64,
+        colour: Color,
+        background: Option<Color>,
+    ) -> Result<()> {
+        let glyphs = text
+            .chars()
+            .map(|ch| Ok((ch, self.glyph(canvas, ch)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Glyphs are at the font's own size, so they're scaled up by `scale` as well.
+        let width = self.width(text).max(1);
+        let height = self.height().max(1);
+        let scale = f64::from(self.scale);
+        let scale_x = f64::from(dest.width()) / f64::from(width) * scale;
+        let scale_y = f64::from(dest.height()) / f64::from(height) * scale;
+        let center = dest.center();
+
+        if let Some(background) = background {
+            let texture = self.atlas.white_page(canvas, background.rgb())?;
+            let pivot = center - dest.top_left();
+            canvas
+                .copy_ex(
+                    texture,
+                    GlyphAtlas::white_rect(),
+                    dest,
+                    angle,
+                    pivot,
+                    false,
+                    false,
+                )
+                .map_err(CatboxError::sdl)?;
+        }
+
+        let mut pen = 0;
+        let mut prev = None;
+        for (ch, glyph) in glyphs {
+            if let Some(prev) = prev {
+                pen += self.unscaled_kerning(prev, ch);
+            }
+            prev = Some(ch);
+
+            if let Some(AtlasRect { page, rect }) = glyph.atlas {
+                let (offset_x, offset_y) = glyph.offset;
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let glyph_dest = Rect::new(
+                    dest.x() + (f64::from(pen + offset_x) * scale_x).round() as i32,
+                    dest.y() + (f64::from(offset_y) * scale_y).round() as i32,
+                    (f64::from(rect.width()) * scale_x).round() as u32,
+                    (f64::from(rect.height()) * scale_y).round() as u32,
+                );
+                let pivot: Point = center - glyph_dest.top_left();
+
+                let texture = self.atlas.page(page, colour.rgb());
+                canvas
+                    .copy_ex(texture, rect, glyph_dest, angle, pivot, false, false)
+                    .map_err(CatboxError::sdl)?;
+            }
+            pen += glyph.advance;
+        }
+
+        Ok(())
+    }
+}
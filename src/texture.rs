@@ -0,0 +1,107 @@
+//! GPU texture caching.
+//!
+//! Uploading a [`Surface`] to the GPU is the most expensive thing a sprite does, so textures are
+//! created lazily and shared between every sprite that was loaded from the same bytes.
+
+use sdl2::{
+    pixels::PixelFormatEnum,
+    render::{BlendMode, Canvas, Texture, TextureCreator},
+    surface::SurfaceRef,
+    video::{Window, WindowContext},
+};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+    rc::{Rc, Weak},
+};
+
+use crate::Result;
+
+/// A texture that keeps the renderer it was created by alive.
+///
+/// Sprites usually outlive the [`Context`](crate::Context) they are drawn with, so the texture
+/// holds its own [`TextureCreator`] to make sure the renderer is destroyed *after* it.
+pub(crate) struct SharedTexture {
+    // Field order matters: the texture has to be dropped before the creator.
+    texture: Texture<'static>,
+    creator: TextureCreator<WindowContext>,
+}
+
+impl SharedTexture {
+    fn new(canvas: &Canvas<Window>, surf: &SurfaceRef) -> Result<Self> {
+        let creator = canvas.texture_creator();
+        let texture = creator.create_texture_from_surface(surf)?;
+        // SAFETY: `Texture` doesn't actually borrow the creator, the lifetime only makes sure the
+        // renderer outlives it. We store a creator for the same renderer right next to the
+        // texture, and the texture is dropped first.
+        let texture = unsafe { std::mem::transmute::<Texture<'_>, Texture<'static>>(texture) };
+
+        Ok(Self { texture, creator })
+    }
+
+    /// Create a blank texture to be filled in with [`Texture::update()`], which blends with what
+    /// it's drawn over.
+    pub(crate) fn blank(canvas: &Canvas<Window>, width: u32, height: u32) -> Result<Self> {
+        let creator = canvas.texture_creator();
+        let mut texture =
+            creator.create_texture_static(PixelFormatEnum::ARGB8888, width, height)?;
+        texture.set_blend_mode(BlendMode::Blend);
+        // SAFETY: see `new()`.
+        let texture = unsafe { std::mem::transmute::<Texture<'_>, Texture<'static>>(texture) };
+
+        Ok(Self { texture, creator })
+    }
+
+    /// Whether this texture was created by the renderer behind `canvas`.
+    pub(crate) fn belongs_to(&self, canvas: &Canvas<Window>) -> bool {
+        self.creator.raw() == canvas.raw()
+    }
+}
+
+impl Deref for SharedTexture {
+    type Target = Texture<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.texture
+    }
+}
+
+impl DerefMut for SharedTexture {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.texture
+    }
+}
+
+/// Textures that are currently alive, keyed by the hash of the bytes they were loaded from.
+#[derive(Default)]
+pub(crate) struct TextureCache {
+    textures: HashMap<u64, Weak<SharedTexture>>,
+}
+
+impl TextureCache {
+    /// Get the texture for `key`, uploading `surf` if no sprite is holding on to one.
+    pub(crate) fn get_or_create(
+        &mut self,
+        key: u64,
+        surf: &SurfaceRef,
+        canvas: &Canvas<Window>,
+    ) -> Result<Rc<SharedTexture>> {
+        if let Some(texture) = self.textures.get(&key).and_then(Weak::upgrade) {
+            return Ok(texture);
+        }
+
+        let texture = Rc::new(SharedTexture::new(canvas, surf)?);
+        self.textures.retain(|_, t| t.strong_count() > 0);
+        self.textures.insert(key, Rc::downgrade(&texture));
+
+        Ok(texture)
+    }
+}
+
+/// Compute the cache key for an image loaded from `bytes`.
+pub(crate) fn texture_key(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}